@@ -5,31 +5,54 @@
 //! Endpoints:
 //! - POST /v1/messages - Chat completions
 
+use std::collections::HashMap;
+
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use thiserror::Error;
+use tokio_util::sync::CancellationToken;
+
+use crate::api::NetworkError;
 
 const BASE_URL: &str = "https://api.anthropic.com";
 const API_VERSION: &str = "2023-06-01";
 const DEFAULT_MODEL: &str = "claude-sonnet-4-20250514";
 
+/// Approximate USD list prices per million (input, output) tokens, matched
+/// against the model id; anything unmatched is priced as Sonnet
+const MODEL_PRICING: &[(&str, f64, f64)] = &[
+    ("opus", 15.0, 75.0),
+    ("sonnet", 3.0, 15.0),
+    ("haiku", 0.8, 4.0),
+];
+
 #[derive(Error, Debug)]
 pub enum AnthropicError {
     #[error("API key not configured")]
     NoApiKey,
-    #[error("Request failed: {0}")]
-    RequestFailed(#[from] reqwest::Error),
+    #[error(transparent)]
+    RequestFailed(NetworkError),
     #[error("Invalid response: {0}")]
     InvalidResponse(String),
     #[error("API error: {error_type} - {message}")]
     ApiError { error_type: String, message: String },
     #[error("Rate limited")]
     RateLimited,
+    #[error("Model declined to respond")]
+    Refused,
+    #[error("Response was cut off at the max_tokens limit")]
+    Truncated,
     #[error("JSON parsing error: {0}")]
     JsonError(#[from] serde_json::Error),
 }
 
+impl From<reqwest::Error> for AnthropicError {
+    fn from(error: reqwest::Error) -> Self {
+        AnthropicError::RequestFailed(error.into())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
     pub role: String, // "user" or "assistant"
@@ -42,6 +65,29 @@ pub struct ChatRequest {
     pub system: Option<String>,
     pub max_tokens: Option<u32>,
     pub temperature: Option<f32>,
+    /// Model to use instead of the client default
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+/// Default generation parameters for an agent; unset fields fall back to
+/// the built-in defaults
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AgentSettings {
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<u32>,
+    pub model: Option<String>,
+}
+
+impl AgentSettings {
+    /// Fill unset fields from `fallback`
+    pub fn or(self, fallback: &AgentSettings) -> AgentSettings {
+        AgentSettings {
+            temperature: self.temperature.or(fallback.temperature),
+            max_tokens: self.max_tokens.or(fallback.max_tokens),
+            model: self.model.or_else(|| fallback.model.clone()),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -51,14 +97,64 @@ pub struct ChatResponse {
     pub usage: Option<Usage>,
 }
 
+impl ChatResponse {
+    /// The model stopped with `stop_reason: "refusal"`
+    pub fn was_refused(&self) -> bool {
+        self.stop_reason.as_deref() == Some("refusal")
+    }
+
+    /// The model ran out of output tokens mid-response
+    pub fn was_truncated(&self) -> bool {
+        self.stop_reason.as_deref() == Some("max_tokens")
+    }
+
+    /// Fail unless the model finished normally, so structured callers don't
+    /// try to parse a refusal or half a JSON object
+    pub fn ensure_complete(&self) -> Result<(), AnthropicError> {
+        if self.was_refused() {
+            Err(AnthropicError::Refused)
+        } else if self.was_truncated() {
+            Err(AnthropicError::Truncated)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Result of a streamed chat, possibly cut short by cancellation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamedChat {
+    pub content: String,
+    pub stop_reason: Option<String>,
+    pub cancelled: bool,
+    /// Token usage reported by the stream; incomplete if it was cancelled
+    pub usage: Option<Usage>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Usage {
     pub input_tokens: u32,
     pub output_tokens: u32,
+    /// Model that served the request, for pricing
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+impl Usage {
+    /// Approximate USD cost of this usage at list prices
+    pub fn estimated_cost_usd(&self) -> f64 {
+        let model = self.model.as_deref().unwrap_or(DEFAULT_MODEL).to_lowercase();
+        let (_, input_price, output_price) = MODEL_PRICING
+            .iter()
+            .find(|(family, _, _)| model.contains(family))
+            .unwrap_or(&MODEL_PRICING[1]);
+        (self.input_tokens as f64 * input_price + self.output_tokens as f64 * output_price)
+            / 1_000_000.0
+    }
 }
 
 /// Agent types for different reasoning tasks
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum AgentType {
     Intent,      // Parse user intent from voice
@@ -68,6 +164,17 @@ pub enum AgentType {
 }
 
 impl AgentType {
+    /// Parse an agent name as sent by the frontend ("intent", "config", ...)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "intent" => Some(AgentType::Intent),
+            "validation" => Some(AgentType::Validation),
+            "config" => Some(AgentType::Config),
+            "general" => Some(AgentType::General),
+            _ => None,
+        }
+    }
+
     /// Get the embedded system prompt for this agent type
     pub fn system_prompt(&self) -> &'static str {
         match self {
@@ -167,6 +274,8 @@ struct MessagesRequest {
     messages: Vec<ApiMessage>,
     #[serde(skip_serializing_if = "Option::is_none")]
     temperature: Option<f32>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -177,6 +286,8 @@ struct ApiMessage {
 
 #[derive(Debug, Clone, Deserialize)]
 struct MessagesResponse {
+    #[serde(default)]
+    model: Option<String>,
     content: Vec<ContentBlock>,
     stop_reason: Option<String>,
     usage: ApiUsage,
@@ -246,6 +357,8 @@ pub struct AnthropicClient {
     api_key: Option<String>,
     base_url: String,
     model: String,
+    prompt_overrides: HashMap<AgentType, String>,
+    agent_settings: HashMap<AgentType, AgentSettings>,
 }
 
 impl AnthropicClient {
@@ -255,6 +368,8 @@ impl AnthropicClient {
             api_key,
             base_url: BASE_URL.to_string(),
             model: DEFAULT_MODEL.to_string(),
+            prompt_overrides: HashMap::new(),
+            agent_settings: HashMap::new(),
         }
     }
 
@@ -270,12 +385,192 @@ impl AnthropicClient {
         self.api_key.as_deref().ok_or(AnthropicError::NoApiKey)
     }
 
+    /// System prompt currently in effect for an agent (override or embedded default)
+    pub fn agent_prompt(&self, agent: AgentType) -> &str {
+        self.prompt_overrides
+            .get(&agent)
+            .map(String::as_str)
+            .unwrap_or_else(|| agent.system_prompt())
+    }
+
+    pub fn set_agent_prompt(&mut self, agent: AgentType, prompt: String) {
+        self.prompt_overrides.insert(agent, prompt);
+    }
+
+    /// Drop any override so the embedded prompt is used again
+    pub fn reset_agent_prompt(&mut self, agent: AgentType) {
+        self.prompt_overrides.remove(&agent);
+    }
+
+    pub fn has_prompt_override(&self, agent: AgentType) -> bool {
+        self.prompt_overrides.contains_key(&agent)
+    }
+
+    pub fn prompt_overrides(&self) -> &HashMap<AgentType, String> {
+        &self.prompt_overrides
+    }
+
+    /// Generation parameters in effect for an agent (stored settings over built-in defaults)
+    pub fn agent_settings(&self, agent: AgentType) -> AgentSettings {
+        let defaults = AgentSettings {
+            temperature: Some(0.3), // Lower temperature for more consistent structured output
+            max_tokens: Some(4096),
+            model: Some(self.model.clone()),
+        };
+        self.agent_settings
+            .get(&agent)
+            .cloned()
+            .unwrap_or_default()
+            .or(&defaults)
+    }
+
+    pub fn set_agent_settings(&mut self, agent: AgentType, settings: AgentSettings) {
+        if settings == AgentSettings::default() {
+            self.agent_settings.remove(&agent);
+        } else {
+            self.agent_settings.insert(agent, settings);
+        }
+    }
+
+    pub fn stored_agent_settings(&self) -> &HashMap<AgentType, AgentSettings> {
+        &self.agent_settings
+    }
+
+    /// Build a request with the agent's prompt and settings, applying per-call overrides
+    fn agent_request(
+        &self,
+        agent: AgentType,
+        messages: Vec<Message>,
+        overrides: AgentSettings,
+    ) -> ChatRequest {
+        let settings = overrides.or(&self.agent_settings(agent));
+        ChatRequest {
+            messages,
+            system: Some(self.agent_prompt(agent).to_string()),
+            max_tokens: settings.max_tokens,
+            temperature: settings.temperature,
+            model: settings.model,
+        }
+    }
+
     /// Send a chat message to Claude
     pub async fn chat(&self, request: ChatRequest) -> Result<ChatResponse, AnthropicError> {
-        let api_key = self.get_api_key()?;
+        let response = self.send_messages(self.messages_request(request, false)).await?;
 
-        let api_request = MessagesRequest {
-            model: self.model.clone(),
+        let messages_response: MessagesResponse = response
+            .json()
+            .await
+            .map_err(|e| AnthropicError::InvalidResponse(e.to_string()))?;
+
+        let content = messages_response
+            .content
+            .iter()
+            .filter_map(|block| block.text.as_ref())
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("");
+
+        Ok(ChatResponse {
+            content,
+            stop_reason: messages_response.stop_reason,
+            usage: Some(Usage {
+                input_tokens: messages_response.usage.input_tokens,
+                output_tokens: messages_response.usage.output_tokens,
+                model: messages_response.model,
+            }),
+        })
+    }
+
+    /// Stream a chat reply, calling `on_text` with each text fragment
+    ///
+    /// If `cancel` fires, the connection is dropped (so generation stops) and
+    /// the text received so far is returned with `cancelled` set.
+    pub async fn chat_stream(
+        &self,
+        request: ChatRequest,
+        cancel: &CancellationToken,
+        mut on_text: impl FnMut(&str),
+    ) -> Result<StreamedChat, AnthropicError> {
+        let mut response = self.send_messages(self.messages_request(request, true)).await?;
+
+        let mut streamed = StreamedChat {
+            content: String::new(),
+            stop_reason: None,
+            cancelled: false,
+            usage: None,
+        };
+        let mut buffer: Vec<u8> = Vec::new();
+
+        loop {
+            let chunk = tokio::select! {
+                biased;
+                _ = cancel.cancelled() => {
+                    streamed.cancelled = true;
+                    break;
+                }
+                chunk = response.chunk() => chunk?,
+            };
+            let Some(chunk) = chunk else { break };
+            buffer.extend_from_slice(&chunk);
+
+            // SSE events are separated by a blank line
+            while let Some(end) = buffer.windows(2).position(|w| w == b"\n\n") {
+                let event: Vec<u8> = buffer.drain(..end + 2).collect();
+                let event = String::from_utf8_lossy(&event);
+
+                for data in event.lines().filter_map(|line| line.strip_prefix("data:")) {
+                    let Ok(value) = serde_json::from_str::<Value>(data.trim()) else {
+                        continue;
+                    };
+                    match value["type"].as_str() {
+                        Some("message_start") => {
+                            let message = &value["message"];
+                            streamed.usage = Some(Usage {
+                                input_tokens: message["usage"]["input_tokens"]
+                                    .as_u64()
+                                    .unwrap_or(0) as u32,
+                                output_tokens: message["usage"]["output_tokens"]
+                                    .as_u64()
+                                    .unwrap_or(0) as u32,
+                                model: message["model"].as_str().map(str::to_string),
+                            });
+                        }
+                        Some("content_block_delta") => {
+                            if let Some(text) = value["delta"]["text"].as_str() {
+                                streamed.content.push_str(text);
+                                on_text(text);
+                            }
+                        }
+                        Some("message_delta") => {
+                            if let Some(reason) = value["delta"]["stop_reason"].as_str() {
+                                streamed.stop_reason = Some(reason.to_string());
+                            }
+                            // Output tokens here are cumulative for the message
+                            if let (Some(usage), Some(output)) =
+                                (&mut streamed.usage, value["usage"]["output_tokens"].as_u64())
+                            {
+                                usage.output_tokens = output as u32;
+                            }
+                        }
+                        Some("error") => {
+                            let error = &value["error"];
+                            return Err(AnthropicError::ApiError {
+                                error_type: error["type"].as_str().unwrap_or("error").to_string(),
+                                message: error["message"].as_str().unwrap_or_default().to_string(),
+                            });
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        Ok(streamed)
+    }
+
+    fn messages_request(&self, request: ChatRequest, stream: bool) -> MessagesRequest {
+        MessagesRequest {
+            model: request.model.unwrap_or_else(|| self.model.clone()),
             max_tokens: request.max_tokens.unwrap_or(4096),
             system: request.system,
             messages: request
@@ -287,7 +582,16 @@ impl AnthropicClient {
                 })
                 .collect(),
             temperature: request.temperature,
-        };
+            stream,
+        }
+    }
+
+    /// POST to the messages endpoint, mapping error statuses
+    async fn send_messages(
+        &self,
+        api_request: MessagesRequest,
+    ) -> Result<reqwest::Response, AnthropicError> {
+        let api_key = self.get_api_key()?;
 
         let response = self
             .client
@@ -316,66 +620,83 @@ impl AnthropicClient {
             });
         }
 
-        let messages_response: MessagesResponse = response
-            .json()
-            .await
-            .map_err(|e| AnthropicError::InvalidResponse(e.to_string()))?;
+        Ok(response)
+    }
 
-        let content = messages_response
-            .content
-            .iter()
-            .filter_map(|block| block.text.as_ref())
-            .cloned()
-            .collect::<Vec<_>>()
-            .join("");
+    /// Chat with a specific agent type (uses the agent's current system prompt)
+    pub async fn chat_with_agent(
+        &self,
+        agent: AgentType,
+        user_message: &str,
+    ) -> Result<ChatResponse, AnthropicError> {
+        let messages = vec![Message {
+            role: "user".to_string(),
+            content: user_message.to_string(),
+        }];
 
-        Ok(ChatResponse {
-            content,
-            stop_reason: messages_response.stop_reason,
-            usage: Some(Usage {
-                input_tokens: messages_response.usage.input_tokens,
-                output_tokens: messages_response.usage.output_tokens,
-            }),
-        })
+        self.chat_with_agent_history(agent, messages).await
     }
 
-    /// Chat with a specific agent type (uses embedded system prompt)
-    pub async fn chat_with_agent(
+    /// Stream a reply from a specific agent type (see `chat_stream`)
+    pub async fn chat_stream_with_agent(
         &self,
         agent: AgentType,
         user_message: &str,
+        cancel: &CancellationToken,
+        on_text: impl FnMut(&str),
+    ) -> Result<StreamedChat, AnthropicError> {
+        let messages = vec![Message {
+            role: "user".to_string(),
+            content: user_message.to_string(),
+        }];
+        let request = self.agent_request(agent, messages, AgentSettings::default());
+
+        self.chat_stream(request, cancel, on_text).await
+    }
+
+    /// Continue a multi-turn conversation with a specific agent type
+    pub async fn chat_with_agent_history(
+        &self,
+        agent: AgentType,
+        messages: Vec<Message>,
     ) -> Result<ChatResponse, AnthropicError> {
-        let request = ChatRequest {
-            messages: vec![Message {
-                role: "user".to_string(),
-                content: user_message.to_string(),
-            }],
-            system: Some(agent.system_prompt().to_string()),
-            max_tokens: Some(4096),
-            temperature: Some(0.3), // Lower temperature for more consistent structured output
-        };
+        self.chat_with_agent_settings(agent, messages, AgentSettings::default())
+            .await
+    }
 
-        self.chat(request).await
+    /// Chat with an agent, overriding its stored settings for this call only
+    pub async fn chat_with_agent_settings(
+        &self,
+        agent: AgentType,
+        messages: Vec<Message>,
+        overrides: AgentSettings,
+    ) -> Result<ChatResponse, AnthropicError> {
+        self.chat(self.agent_request(agent, messages, overrides)).await
     }
 
     /// Parse user intent from natural language
     pub async fn parse_intent(&self, user_input: &str) -> Result<ParsedIntent, AnthropicError> {
         let response = self.chat_with_agent(AgentType::Intent, user_input).await?;
+        response.ensure_complete()?;
         let json_str = extract_json(&response.content)?;
         let parsed: ParsedIntent = serde_json::from_str(&json_str)?;
         Ok(parsed)
     }
 
-    /// Validate data samples
-    pub async fn validate_data(&self, data_samples: &str) -> Result<ValidationResult, AnthropicError> {
+    /// Validate data samples, returning the call's usage alongside the result
+    pub async fn validate_data(
+        &self,
+        data_samples: &str,
+    ) -> Result<(ValidationResult, Option<Usage>), AnthropicError> {
         let prompt = format!(
             "Please validate the following data samples:\n\n```\n{}\n```",
             data_samples
         );
         let response = self.chat_with_agent(AgentType::Validation, &prompt).await?;
+        response.ensure_complete()?;
         let json_str = extract_json(&response.content)?;
         let result: ValidationResult = serde_json::from_str(&json_str)?;
-        Ok(result)
+        Ok((result, response.usage))
     }
 
     /// Get configuration recommendations
@@ -394,6 +715,7 @@ impl AnthropicClient {
         };
 
         let response = self.chat_with_agent(AgentType::Config, &prompt).await?;
+        response.ensure_complete()?;
         let json_str = extract_json(&response.content)?;
         let result: ConfigRecommendation = serde_json::from_str(&json_str)?;
         Ok(result)
@@ -412,6 +734,7 @@ impl AnthropicClient {
                 content: "Hi".to_string(),
             }],
             temperature: None,
+            stream: false,
         };
 
         let response = self
@@ -429,7 +752,7 @@ impl AnthropicClient {
 }
 
 /// Extract JSON from a response that may contain markdown code blocks
-fn extract_json(content: &str) -> Result<String, AnthropicError> {
+pub fn extract_json(content: &str) -> Result<String, AnthropicError> {
     // Try to find JSON in code blocks first
     if let Some(start) = content.find("```json") {
         let json_start = start + 7;
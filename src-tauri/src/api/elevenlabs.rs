@@ -9,36 +9,76 @@
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use thiserror::Error;
+use tokio_util::sync::CancellationToken;
+
+use crate::api::NetworkError;
 
 const BASE_URL: &str = "https://api.elevenlabs.io";
 const DEFAULT_VOICE_ID: &str = "21m00Tcm4TlvDq8ikWAM"; // Rachel voice
+/// Approximate USD price per 1,000 credits (varies by subscription tier)
+const USD_PER_1K_CREDITS: f64 = 0.30;
+/// Below this sample rate speech-to-text accuracy drops noticeably
+const MIN_RECOMMENDED_SAMPLE_RATE: u32 = 16_000;
+/// Below this bitrate compressed recordings lose consonant detail
+const MIN_RECOMMENDED_BITRATE_KBPS: u32 = 32;
+/// WebM track metadata sits at the start of the file; don't scan the media data
+const WEBM_HEADER_SCAN_BYTES: usize = 64 * 1024;
 
 #[derive(Error, Debug)]
 pub enum ElevenLabsError {
     #[error("API key not configured")]
     NoApiKey,
-    #[error("Request failed: {0}")]
-    RequestFailed(#[from] reqwest::Error),
+    #[error(transparent)]
+    RequestFailed(NetworkError),
     #[error("Invalid response: {0}")]
     InvalidResponse(String),
     #[error("API error: {status} - {message}")]
     ApiError { status: u16, message: String },
+    #[error("Character quota exceeded: {0}")]
+    QuotaExceeded(String),
+    #[error("Incompatible voice: {0}")]
+    IncompatibleVoice(String),
     #[error("Base64 decode error: {0}")]
     Base64Error(#[from] base64::DecodeError),
 }
 
+impl From<reqwest::Error> for ElevenLabsError {
+    fn from(error: reqwest::Error) -> Self {
+        ElevenLabsError::RequestFailed(error.into())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TranscriptionResult {
     pub text: String,
     pub confidence: Option<f32>,
     pub language_code: Option<String>,
+    /// What could be read from the clip's headers
+    pub audio: AudioInfo,
+    /// Set when the recording quality is likely to hurt accuracy
+    pub quality_warning: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AudioInfo {
+    pub mime_type: String,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u16>,
+    pub bitrate_kbps: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SpeechResult {
     pub audio_base64: String,
     pub content_type: String,
+    /// Synthesis was interrupted; the audio covers only the chunks completed
+    #[serde(default)]
+    pub cancelled: bool,
+    /// Voice/model combination works but may sound poor
+    #[serde(default)]
+    pub warning: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -105,6 +145,8 @@ pub struct ElevenLabsClient {
     base_url: String,
     default_voice_id: String,
     default_model_id: String,
+    /// Voice metadata from the last `list_voices` call, keyed by voice id
+    voices: HashMap<String, Voice>,
 }
 
 impl ElevenLabsClient {
@@ -115,6 +157,7 @@ impl ElevenLabsClient {
             base_url: BASE_URL.to_string(),
             default_voice_id: DEFAULT_VOICE_ID.to_string(),
             default_model_id: "eleven_multilingual_v2".to_string(),
+            voices: HashMap::new(),
         }
     }
 
@@ -137,10 +180,17 @@ impl ElevenLabsClient {
         // Decode base64 audio data
         let audio_bytes = BASE64.decode(audio_base64)?;
 
+        let audio = inspect_audio(&audio_bytes);
+        let quality_warning = audio_quality_warning(&audio);
+        if let Some(warning) = &quality_warning {
+            tracing::warn!("{}", warning);
+        }
+
         // Create multipart form with audio file
+        let (file_name, mime) = detect_audio_format(&audio_bytes);
         let part = reqwest::multipart::Part::bytes(audio_bytes)
-            .file_name("audio.webm")
-            .mime_str("audio/webm")
+            .file_name(file_name)
+            .mime_str(mime)
             .map_err(|e| ElevenLabsError::InvalidResponse(e.to_string()))?;
 
         let form = reqwest::multipart::Form::new()
@@ -158,10 +208,7 @@ impl ElevenLabsClient {
         let status = response.status();
         if !status.is_success() {
             let error_text = response.text().await.unwrap_or_default();
-            return Err(ElevenLabsError::ApiError {
-                status: status.as_u16(),
-                message: error_text,
-            });
+            return Err(api_error(status.as_u16(), error_text));
         }
 
         let transcription: TranscriptionResponse = response
@@ -173,24 +220,78 @@ impl ElevenLabsClient {
             text: transcription.text,
             confidence: transcription.confidence,
             language_code: transcription.language_code,
+            audio,
+            quality_warning,
         })
     }
 
+    pub fn default_model_id(&self) -> &str {
+        &self.default_model_id
+    }
+
     /// Convert text to speech using ElevenLabs TTS API
+    ///
+    /// Text longer than the model's per-request limit is split into chunks
+    /// and the resulting audio is concatenated. If `cancel` fires, synthesis
+    /// stops after the current chunk and the audio produced so far is returned.
     pub async fn text_to_speech(
         &self,
         text: &str,
         voice_id: Option<&str>,
         voice_settings: Option<VoiceSettings>,
+        cancel: Option<&CancellationToken>,
     ) -> Result<SpeechResult, ElevenLabsError> {
-        let api_key = self.get_api_key()?;
         let voice = voice_id.unwrap_or(&self.default_voice_id);
         let settings = voice_settings.unwrap_or_default();
 
+        // Only voices seen via list_voices can be checked without an extra request
+        let warning = match self.voices.get(voice) {
+            Some(metadata) => check_voice_compatibility(metadata, &self.default_model_id, text)?,
+            None => None,
+        };
+        if let Some(warning) = &warning {
+            tracing::warn!("{}", warning);
+        }
+
+        let mut chunks = chunk_text(text, model_char_limit(&self.default_model_id));
+        if chunks.is_empty() {
+            chunks.push(text.to_string());
+        }
+
+        let mut audio_bytes = Vec::new();
+        let mut content_type = "audio/mpeg".to_string();
+        let mut cancelled = false;
+        for chunk in &chunks {
+            if cancel.is_some_and(|c| c.is_cancelled()) {
+                cancelled = true;
+                break;
+            }
+            let (bytes, chunk_content_type) = self.synthesize_chunk(chunk, voice, &settings).await?;
+            audio_bytes.extend_from_slice(&bytes);
+            content_type = chunk_content_type;
+        }
+
+        Ok(SpeechResult {
+            audio_base64: BASE64.encode(&audio_bytes),
+            content_type,
+            cancelled,
+            warning,
+        })
+    }
+
+    /// Synthesize a single request-sized chunk of text
+    async fn synthesize_chunk(
+        &self,
+        text: &str,
+        voice: &str,
+        settings: &VoiceSettings,
+    ) -> Result<(Vec<u8>, String), ElevenLabsError> {
+        let api_key = self.get_api_key()?;
+
         let request = TextToSpeechRequest {
             text: text.to_string(),
             model_id: self.default_model_id.clone(),
-            voice_settings: settings,
+            voice_settings: settings.clone(),
         };
 
         let response = self
@@ -208,10 +309,7 @@ impl ElevenLabsClient {
         let status = response.status();
         if !status.is_success() {
             let error_text = response.text().await.unwrap_or_default();
-            return Err(ElevenLabsError::ApiError {
-                status: status.as_u16(),
-                message: error_text,
-            });
+            return Err(api_error(status.as_u16(), error_text));
         }
 
         let content_type = response
@@ -222,12 +320,8 @@ impl ElevenLabsClient {
             .to_string();
 
         let audio_bytes = response.bytes().await?;
-        let audio_base64 = BASE64.encode(&audio_bytes);
 
-        Ok(SpeechResult {
-            audio_base64,
-            content_type,
-        })
+        Ok((audio_bytes.to_vec(), content_type))
     }
 
     /// Test API connection by fetching user info
@@ -244,8 +338,45 @@ impl ElevenLabsClient {
         Ok(response.status().is_success())
     }
 
-    /// List available voices
-    pub async fn list_voices(&self) -> Result<Vec<Voice>, ElevenLabsError> {
+    /// Character quota for the account's current billing period
+    pub async fn get_quota(&self) -> Result<VoiceQuota, ElevenLabsError> {
+        let api_key = self.get_api_key()?;
+
+        let response = self
+            .client
+            .get(format!("{}/v1/user", self.base_url))
+            .header("xi-api-key", api_key)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(api_error(status.as_u16(), error_text));
+        }
+
+        let user: UserResponse = response
+            .json()
+            .await
+            .map_err(|e| ElevenLabsError::InvalidResponse(e.to_string()))?;
+        let subscription = user.subscription;
+
+        Ok(VoiceQuota {
+            tier: subscription.tier,
+            character_count: subscription.character_count,
+            character_limit: subscription.character_limit,
+            characters_remaining: subscription
+                .character_limit
+                .saturating_sub(subscription.character_count),
+            next_reset: subscription
+                .next_character_count_reset_unix
+                .and_then(|ts| chrono::DateTime::from_timestamp(ts, 0))
+                .map(|reset| reset.to_rfc3339()),
+        })
+    }
+
+    /// List available voices, caching their metadata for compatibility checks
+    pub async fn list_voices(&mut self) -> Result<Vec<Voice>, ElevenLabsError> {
         let api_key = self.get_api_key()?;
 
         let response = self
@@ -258,10 +389,7 @@ impl ElevenLabsClient {
         let status = response.status();
         if !status.is_success() {
             let error_text = response.text().await.unwrap_or_default();
-            return Err(ElevenLabsError::ApiError {
-                status: status.as_u16(),
-                message: error_text,
-            });
+            return Err(api_error(status.as_u16(), error_text));
         }
 
         let voices_response: VoicesResponse = response
@@ -269,17 +397,429 @@ impl ElevenLabsClient {
             .await
             .map_err(|e| ElevenLabsError::InvalidResponse(e.to_string()))?;
 
+        self.voices = voices_response
+            .voices
+            .iter()
+            .map(|v| (v.voice_id.clone(), v.clone()))
+            .collect();
+
         Ok(voices_response.voices)
     }
 }
 
+/// Map a non-2xx response to an error, recognising quota exhaustion from the detail
+fn api_error(status: u16, body: String) -> ElevenLabsError {
+    let detail = serde_json::from_str::<ApiErrorResponse>(&body)
+        .ok()
+        .and_then(|r| r.detail);
+
+    if let Some(detail) = detail {
+        if detail.status.as_deref() == Some("quota_exceeded") {
+            return ElevenLabsError::QuotaExceeded(
+                detail
+                    .message
+                    .unwrap_or_else(|| "No character credits remaining".to_string()),
+            );
+        }
+    }
+
+    ElevenLabsError::ApiError {
+        status,
+        message: body,
+    }
+}
+
+/// Guess the container from magic bytes (browser recordings default to WebM)
+pub fn detect_audio_format(bytes: &[u8]) -> (&'static str, &'static str) {
+    if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WAVE".as_slice()) {
+        ("audio.wav", "audio/wav")
+    } else if bytes.starts_with(b"OggS") {
+        ("audio.ogg", "audio/ogg")
+    } else if bytes.starts_with(b"ID3") || bytes.starts_with(&[0xFF, 0xFB]) {
+        ("audio.mp3", "audio/mpeg")
+    } else if bytes.get(4..8) == Some(b"ftyp".as_slice()) {
+        ("audio.m4a", "audio/mp4")
+    } else {
+        ("audio.webm", "audio/webm")
+    }
+}
+
+/// Read sample rate, channels and bitrate from a clip's container headers
+///
+/// Handles WAV, MP3, Ogg (Opus/Vorbis) and WebM; fields that can't be read
+/// without fully decoding the audio are left as `None`.
+pub fn inspect_audio(bytes: &[u8]) -> AudioInfo {
+    let (_, mime) = detect_audio_format(bytes);
+    let mut info = AudioInfo {
+        mime_type: mime.to_string(),
+        ..Default::default()
+    };
+
+    match mime {
+        "audio/wav" => inspect_wav(bytes, &mut info),
+        "audio/mpeg" => inspect_mp3(bytes, &mut info),
+        "audio/ogg" => inspect_ogg(bytes, &mut info),
+        "audio/webm" => inspect_webm(bytes, &mut info),
+        _ => {}
+    }
+
+    info
+}
+
+/// Explain why a clip may transcribe poorly, if it falls below recommended quality
+pub fn audio_quality_warning(info: &AudioInfo) -> Option<String> {
+    if let Some(rate) = info.sample_rate.filter(|r| *r < MIN_RECOMMENDED_SAMPLE_RATE) {
+        return Some(format!(
+            "Audio is sampled at {} Hz (recommended at least {} Hz); check your microphone settings",
+            rate, MIN_RECOMMENDED_SAMPLE_RATE
+        ));
+    }
+    if info.mime_type != "audio/wav" {
+        if let Some(kbps) = info.bitrate_kbps.filter(|b| *b < MIN_RECOMMENDED_BITRATE_KBPS) {
+            return Some(format!(
+                "Audio is compressed to {} kbps (recommended at least {} kbps); accuracy may suffer",
+                kbps, MIN_RECOMMENDED_BITRATE_KBPS
+            ));
+        }
+    }
+    None
+}
+
+fn read_u16_le(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn read_u32_le(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn inspect_wav(bytes: &[u8], info: &mut AudioInfo) {
+    // Walk RIFF chunks looking for "fmt "
+    let mut offset = 12;
+    while let (Some(id), Some(size)) = (bytes.get(offset..offset + 4), read_u32_le(bytes, offset + 4))
+    {
+        if id == b"fmt " {
+            let byte_rate = read_u32_le(bytes, offset + 16);
+            info.channels = read_u16_le(bytes, offset + 10);
+            info.sample_rate = read_u32_le(bytes, offset + 12);
+            info.bitrate_kbps = byte_rate.map(|rate| rate * 8 / 1000);
+            return;
+        }
+        offset += 8 + size as usize + (size as usize % 2);
+    }
+}
+
+fn inspect_mp3(bytes: &[u8], info: &mut AudioInfo) {
+    // Skip an ID3v2 tag (its size is stored as a 28-bit syncsafe integer)
+    let mut offset = 0;
+    if bytes.starts_with(b"ID3") && bytes.len() >= 10 {
+        let size = bytes[6..10].iter().fold(0usize, |acc, b| (acc << 7) | (*b as usize & 0x7F));
+        offset = 10 + size;
+    }
+
+    let Some(frame) = bytes
+        .get(offset..)
+        .and_then(|rest| rest.windows(4).find(|w| w[0] == 0xFF && w[1] & 0xE0 == 0xE0))
+    else {
+        return;
+    };
+
+    let version = (frame[1] >> 3) & 0x03; // 3 = MPEG-1, 2 = MPEG-2, 0 = MPEG-2.5
+    let layer = (frame[1] >> 1) & 0x03; // 1 = Layer III
+    if layer != 1 || version == 1 {
+        return;
+    }
+
+    const MPEG1_KBPS: [u32; 15] =
+        [0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320];
+    const MPEG2_KBPS: [u32; 15] = [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160];
+    const BASE_RATES: [u32; 3] = [44_100, 48_000, 32_000];
+
+    let bitrate_index = (frame[2] >> 4) as usize;
+    let rate_index = ((frame[2] >> 2) & 0x03) as usize;
+    let table = if version == 3 { &MPEG1_KBPS } else { &MPEG2_KBPS };
+
+    info.bitrate_kbps = table.get(bitrate_index).copied().filter(|b| *b > 0);
+    info.sample_rate = BASE_RATES.get(rate_index).map(|rate| match version {
+        3 => *rate,
+        2 => rate / 2,
+        _ => rate / 4,
+    });
+    info.channels = Some(if frame[3] >> 6 == 3 { 1 } else { 2 });
+}
+
+fn inspect_ogg(bytes: &[u8], info: &mut AudioInfo) {
+    // The first page holds the codec identification header
+    let Some(&segments) = bytes.get(26) else { return };
+    let Some(packet) = bytes.get(27 + segments as usize..) else { return };
+
+    if packet.starts_with(b"OpusHead") {
+        info.channels = packet.get(9).map(|c| *c as u16);
+        // Opus always decodes at 48kHz; this is the original input rate
+        info.sample_rate = read_u32_le(packet, 12).filter(|r| *r > 0);
+    } else if packet.starts_with(b"\x01vorbis") {
+        info.channels = packet.get(11).map(|c| *c as u16);
+        info.sample_rate = read_u32_le(packet, 12);
+        info.bitrate_kbps = read_u32_le(packet, 20)
+            .filter(|b| *b > 0 && *b < i32::MAX as u32)
+            .map(|b| b / 1000);
+    }
+}
+
+fn inspect_webm(bytes: &[u8], info: &mut AudioInfo) {
+    // Find an Audio element (ID 0xE1) whose children include a plausible
+    // SamplingFrequency (0xB5); Channels is 0x9F
+    let header_region = &bytes[..bytes.len().min(WEBM_HEADER_SCAN_BYTES)];
+    for start in header_region.iter().enumerate().filter(|(_, b)| **b == 0xE1).map(|(i, _)| i) {
+        let Some((size, header)) = read_ebml_size(bytes, start + 1) else {
+            continue;
+        };
+        let Some(body) = bytes.get(start + 1 + header..start + 1 + header + size) else {
+            continue;
+        };
+
+        let mut sample_rate = None;
+        let mut channels = None;
+        let mut offset = 0;
+        while let (Some(&id), Some((len, len_header))) =
+            (body.get(offset), read_ebml_size(body, offset + 1))
+        {
+            let value_start = offset + 1 + len_header;
+            let Some(value) = body.get(value_start..value_start + len) else {
+                break;
+            };
+            match (id, value.len()) {
+                (0xB5, 4) => sample_rate = value.try_into().ok().map(|v| f32::from_be_bytes(v) as f64),
+                (0xB5, 8) => sample_rate = value.try_into().ok().map(f64::from_be_bytes),
+                (0x9F, 1..=8) => channels = Some(value.iter().fold(0u64, |acc, b| (acc << 8) | *b as u64)),
+                _ => {}
+            }
+            offset = value_start + len;
+        }
+
+        if let Some(rate) = sample_rate.filter(|r| (1_000.0..=384_000.0).contains(r)) {
+            info.sample_rate = Some(rate as u32);
+            info.channels = channels.map(|c| c as u16);
+            return;
+        }
+    }
+}
+
+/// Decode an EBML variable-length size, returning (value, bytes used)
+fn read_ebml_size(bytes: &[u8], at: usize) -> Option<(usize, usize)> {
+    let first = *bytes.get(at)?;
+    let len = first.leading_zeros() as usize + 1;
+    if len > 8 {
+        return None;
+    }
+    let mut value = (first as u64) & (0xFF >> len);
+    for byte in bytes.get(at + 1..at + len)? {
+        value = (value << 8) | *byte as u64;
+    }
+    Some((value as usize, len))
+}
+
+/// Maximum characters ElevenLabs accepts in a single TTS request for a model
+pub fn model_char_limit(model_id: &str) -> usize {
+    match model_id {
+        "eleven_flash_v2_5" | "eleven_turbo_v2_5" => 40_000,
+        "eleven_flash_v2" | "eleven_turbo_v2" => 30_000,
+        _ => 10_000,
+    }
+}
+
+/// Languages a model can speak, or `None` if it is multilingual
+pub fn model_languages(model_id: &str) -> Option<&'static [&'static str]> {
+    match model_id {
+        "eleven_monolingual_v1" | "eleven_english_sts_v2" | "eleven_turbo_v2" | "eleven_flash_v2" => {
+            Some(&["en"])
+        }
+        _ => None,
+    }
+}
+
+/// Language a voice was built for, from its `language` label
+pub fn voice_language(voice: &Voice) -> Option<String> {
+    voice
+        .labels
+        .as_ref()
+        .and_then(|labels| labels.get("language"))
+        .map(|language| language.to_lowercase())
+}
+
+/// Check a voice against a model before synthesis
+///
+/// Returns an error when the model can't speak the voice's language, and a
+/// warning when an English voice is asked to read mostly non-Latin text.
+pub fn check_voice_compatibility(
+    voice: &Voice,
+    model_id: &str,
+    text: &str,
+) -> Result<Option<String>, ElevenLabsError> {
+    let Some(language) = voice_language(voice) else {
+        return Ok(None);
+    };
+
+    match model_languages(model_id) {
+        Some(supported) if !supported.contains(&language.as_str()) => {
+            Err(ElevenLabsError::IncompatibleVoice(format!(
+                "voice '{}' is a '{}' voice but model '{}' only supports: {}",
+                voice.name,
+                language,
+                model_id,
+                supported.join(", ")
+            )))
+        }
+        None if language == "en" && is_mostly_non_ascii(text) => Ok(Some(format!(
+            "voice '{}' is English-only; non-English text may be accented or unclear",
+            voice.name
+        ))),
+        _ => Ok(None),
+    }
+}
+
+fn is_mostly_non_ascii(text: &str) -> bool {
+    let letters: Vec<char> = text.chars().filter(|c| c.is_alphabetic()).collect();
+    let non_ascii = letters.iter().filter(|c| !c.is_ascii()).count();
+    !letters.is_empty() && non_ascii * 2 > letters.len()
+}
+
+/// Credits billed per character for a model (Flash/Turbo bill at half rate)
+pub fn credits_per_character(model_id: &str) -> f64 {
+    if model_id.contains("flash") || model_id.contains("turbo") {
+        0.5
+    } else {
+        1.0
+    }
+}
+
+/// Approximate USD cost of a number of credits
+pub fn credits_to_usd(credits: f64) -> f64 {
+    credits / 1000.0 * USD_PER_1K_CREDITS
+}
+
+/// Split text into chunks of at most `max_chars`, breaking on sentence
+/// boundaries first, then words, then characters
+pub fn chunk_text(text: &str, max_chars: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut current_len = 0;
+
+    for sentence in split_sentences(text) {
+        for piece in split_to_fit(sentence, max_chars) {
+            let piece_len = piece.chars().count();
+            if current_len > 0 && current_len + 1 + piece_len > max_chars {
+                chunks.push(std::mem::take(&mut current));
+                current_len = 0;
+            }
+            if current_len > 0 {
+                current.push(' ');
+                current_len += 1;
+            }
+            current.push_str(&piece);
+            current_len += piece_len;
+        }
+    }
+
+    if current_len > 0 {
+        chunks.push(current);
+    }
+
+    chunks
+}
+
+/// Split text after sentence-ending punctuation or newlines
+fn split_sentences(text: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        let at_boundary = match c {
+            '\n' => true,
+            '.' | '!' | '?' => chars.peek().map(|(_, next)| next.is_whitespace()).unwrap_or(true),
+            _ => false,
+        };
+        if at_boundary {
+            let end = i + c.len_utf8();
+            sentences.push(&text[start..end]);
+            start = end;
+        }
+    }
+    sentences.push(&text[start..]);
+
+    sentences
+        .into_iter()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Break a single sentence into pieces of at most `max_chars`
+fn split_to_fit(sentence: &str, max_chars: usize) -> Vec<String> {
+    if sentence.chars().count() <= max_chars {
+        return vec![sentence.to_string()];
+    }
+
+    let mut pieces = Vec::new();
+    let mut current = String::new();
+    let mut current_len = 0;
+
+    for word in sentence.split_whitespace() {
+        let chars: Vec<char> = word.chars().collect();
+        for part in chars.chunks(max_chars.max(1)) {
+            if current_len > 0 && current_len + 1 + part.len() > max_chars {
+                pieces.push(std::mem::take(&mut current));
+                current_len = 0;
+            }
+            if current_len > 0 {
+                current.push(' ');
+                current_len += 1;
+            }
+            current.extend(part);
+            current_len += part.len();
+        }
+    }
+
+    if current_len > 0 {
+        pieces.push(current);
+    }
+
+    pieces
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Voice {
     pub voice_id: String,
     pub name: String,
     pub category: Option<String>,
     pub description: Option<String>,
-    pub labels: Option<std::collections::HashMap<String, String>>,
+    pub labels: Option<HashMap<String, String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoiceQuota {
+    pub tier: Option<String>,
+    pub character_count: u64,
+    pub character_limit: u64,
+    pub characters_remaining: u64,
+    /// When the character count resets (RFC 3339)
+    pub next_reset: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct UserResponse {
+    subscription: SubscriptionInfo,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct SubscriptionInfo {
+    #[serde(default)]
+    tier: Option<String>,
+    character_count: u64,
+    character_limit: u64,
+    #[serde(default)]
+    next_character_count_reset_unix: Option<i64>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -1,3 +1,5 @@
+use thiserror::Error;
+
 pub mod anthropic;
 pub mod elevenlabs;
 pub mod tinker;
@@ -10,3 +12,102 @@ pub use elevenlabs::ElevenLabsClient;
 pub use tinker::TinkerClient;
 pub use tonic::TonicClient;
 pub use yutori::YutoriClient;
+
+/// Fields commonly found in a JSON error body, either at the top level or
+/// nested under `error` (`{"message", "code", "details"}`)
+pub struct ErrorBody {
+    pub message: String,
+    pub code: Option<String>,
+    pub details: Option<serde_json::Value>,
+}
+
+impl ErrorBody {
+    /// Parse a structured error from a response body, falling back to the raw text
+    pub fn parse(body: &str) -> Self {
+        let raw = || Self {
+            message: body.to_string(),
+            code: None,
+            details: None,
+        };
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(body) else {
+            return raw();
+        };
+
+        let error = match value.get("error") {
+            Some(nested) if nested.is_object() => nested,
+            _ => &value,
+        };
+        let text = |key: &str| match error.get(key)? {
+            serde_json::Value::String(s) => Some(s.clone()),
+            serde_json::Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        };
+
+        let message = text("message")
+            .or_else(|| text("detail"))
+            .or_else(|| value.get("error").and_then(|e| e.as_str()).map(str::to_string));
+        let code = text("code").or_else(|| text("type"));
+        let details = error.get("details").filter(|d| !d.is_null()).cloned();
+
+        match message {
+            Some(message) => Self { message, code, details },
+            None if code.is_some() || details.is_some() => Self {
+                message: body.to_string(),
+                code,
+                details,
+            },
+            None => raw(),
+        }
+    }
+}
+
+/// " (code)" for error display, or nothing when there's no code
+pub fn code_suffix(code: &Option<String>) -> String {
+    code.as_ref().map(|c| format!(" ({})", c)).unwrap_or_default()
+}
+
+/// A request that failed before any HTTP response arrived, classified so the
+/// UI can tell "you're offline" apart from a slow or misconfigured service
+#[derive(Error, Debug)]
+pub enum NetworkError {
+    #[error("Network unavailable: you appear to be offline")]
+    Offline,
+    #[error("Request timed out")]
+    Timeout,
+    #[error("TLS error: {0}")]
+    Tls(String),
+    #[error("Request failed: {0}")]
+    Other(reqwest::Error),
+}
+
+impl From<reqwest::Error> for NetworkError {
+    fn from(error: reqwest::Error) -> Self {
+        if error.is_timeout() {
+            return NetworkError::Timeout;
+        }
+        if !error.is_connect() {
+            return NetworkError::Other(error);
+        }
+
+        // Connect errors cover DNS failures and unreachable hosts, but also
+        // TLS handshake failures, which only show up in the source chain
+        let mut causes = Vec::new();
+        let mut source = std::error::Error::source(&error);
+        while let Some(cause) = source {
+            causes.push(cause.to_string());
+            source = cause.source();
+        }
+        let is_tls = causes.iter().any(|cause| {
+            let cause = cause.to_lowercase();
+            ["certificate", "tls", "ssl", "handshake"]
+                .iter()
+                .any(|marker| cause.contains(marker))
+        });
+
+        if is_tls {
+            NetworkError::Tls(causes.pop().unwrap_or_else(|| error.to_string()))
+        } else {
+            NetworkError::Offline
+        }
+    }
+}
@@ -8,14 +8,16 @@ use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::api::NetworkError;
+
 const BASE_URL: &str = "https://api.thinkingmachines.ai";
 
 #[derive(Error, Debug)]
 pub enum TinkerError {
     #[error("API key not configured")]
     NoApiKey,
-    #[error("Request failed: {0}")]
-    RequestFailed(#[from] reqwest::Error),
+    #[error(transparent)]
+    RequestFailed(NetworkError),
     #[error("Invalid response: {0}")]
     InvalidResponse(String),
     #[error("Training failed: {0}")]
@@ -28,6 +30,12 @@ pub enum TinkerError {
     ApiError { status: u16, message: String },
 }
 
+impl From<reqwest::Error> for TinkerError {
+    fn from(error: reqwest::Error) -> Self {
+        TinkerError::RequestFailed(error.into())
+    }
+}
+
 // ============ Training Configuration Types ============
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -52,6 +60,48 @@ pub enum TrainingType {
     Gkd,
 }
 
+impl TrainingType {
+    /// Canonical lowercase name, matching the serialized form
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TrainingType::Sft => "sft",
+            TrainingType::Rl => "rl",
+            TrainingType::Grpo => "grpo",
+            TrainingType::Ppo => "ppo",
+            TrainingType::Dpo => "dpo",
+            TrainingType::Gkd => "gkd",
+        }
+    }
+
+    /// Resolve a canonical name or common synonym ("supervised", "rlhf", ...)
+    pub fn from_alias(name: &str) -> Option<Self> {
+        let normalized = name
+            .trim()
+            .to_lowercase()
+            .replace(['-', '_'], " ")
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let training_type = match normalized.as_str() {
+            "sft" | "supervised" | "supervised fine tuning" | "supervised finetuning"
+            | "fine tuning" | "finetuning" | "finetune" | "fine tune" | "instruction tuning"
+            | "instruction fine tuning" | "instruct" => TrainingType::Sft,
+            "dpo" | "preference" | "preference tuning" | "preference optimization"
+            | "direct preference optimization" => TrainingType::Dpo,
+            "rl" | "rlhf" | "reinforcement learning" | "reinforcement" => TrainingType::Rl,
+            "ppo" | "proximal policy optimization" => TrainingType::Ppo,
+            "grpo" | "group relative policy optimization" => TrainingType::Grpo,
+            "gkd" | "distillation" | "knowledge distillation" | "generalized knowledge distillation" => {
+                TrainingType::Gkd
+            }
+            _ => return None,
+        };
+
+        Some(training_type)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Hyperparameters {
     pub learning_rate: f64,
@@ -84,6 +134,9 @@ pub struct TrainingRun {
     pub updated_at: DateTime<Utc>,
     pub progress: Option<TrainingProgress>,
     pub error: Option<String>,
+    /// Configuration the run was created with, when the API returns it
+    #[serde(default)]
+    pub config: Option<TrainingConfig>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -142,6 +195,24 @@ pub struct ListCheckpointsResponse {
     pub per_page: u32,
 }
 
+// ============ Log Types ============
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogLine {
+    pub timestamp: DateTime<Utc>,
+    #[serde(default)]
+    pub level: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunLogs {
+    pub lines: Vec<LogLine>,
+    /// Pass as `since` to fetch only newer lines
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+}
+
 // ============ Model Information ============
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -152,6 +223,9 @@ pub struct ModelInfo {
     pub supported_training_types: Vec<TrainingType>,
     pub max_lora_rank: u32,
     pub price_per_million_tokens: f64,
+    /// Module names LoRA can target on this architecture (empty when not reported)
+    #[serde(default)]
+    pub target_modules: Vec<String>,
 }
 
 // ============ API Request/Response Types ============
@@ -488,6 +562,53 @@ impl TinkerClient {
         Ok(checkpoint)
     }
 
+    /// Get log lines for a training run, starting after the `since` cursor
+    pub async fn get_run_logs(
+        &self,
+        run_id: &str,
+        since: Option<&str>,
+    ) -> Result<RunLogs, TinkerError> {
+        let mut request = self
+            .client
+            .get(format!("{}/v1/training/runs/{}/logs", self.base_url, run_id))
+            .header("Authorization", self.auth_header()?);
+        if let Some(since) = since {
+            request = request.query(&[("since", since)]);
+        }
+        let response = request.send().await?;
+
+        let status = response.status();
+
+        if status == 401 {
+            return Err(TinkerError::Unauthorized);
+        }
+
+        if status == 404 {
+            return Err(TinkerError::NotFound(run_id.to_string()));
+        }
+
+        if !status.is_success() {
+            let error: ApiError = response
+                .json()
+                .await
+                .unwrap_or(ApiError {
+                    message: "Unknown error".to_string(),
+                    code: None,
+                });
+            return Err(TinkerError::ApiError {
+                status: status.as_u16(),
+                message: error.message,
+            });
+        }
+
+        let logs: RunLogs = response
+            .json()
+            .await
+            .map_err(|e| TinkerError::InvalidResponse(e.to_string()))?;
+
+        Ok(logs)
+    }
+
     /// Get available models
     pub async fn get_models(&self) -> Result<Vec<ModelInfo>, TinkerError> {
         let response = self
@@ -574,6 +695,101 @@ impl TinkerClient {
         Ok(upload_response)
     }
 
+    /// Get metadata for an uploaded dataset
+    pub async fn get_dataset(&self, dataset_id: &str) -> Result<DatasetInfo, TinkerError> {
+        let response = self
+            .client
+            .get(format!("{}/v1/datasets/{}", self.base_url, dataset_id))
+            .header("Authorization", self.auth_header()?)
+            .send()
+            .await?;
+
+        let status = response.status();
+
+        if status == 401 {
+            return Err(TinkerError::Unauthorized);
+        }
+
+        if status == 404 {
+            return Err(TinkerError::NotFound(dataset_id.to_string()));
+        }
+
+        if !status.is_success() {
+            let error: ApiError = response
+                .json()
+                .await
+                .unwrap_or(ApiError {
+                    message: "Unknown error".to_string(),
+                    code: None,
+                });
+            return Err(TinkerError::ApiError {
+                status: status.as_u16(),
+                message: error.message,
+            });
+        }
+
+        let dataset: DatasetInfo = response
+            .json()
+            .await
+            .map_err(|e| TinkerError::InvalidResponse(e.to_string()))?;
+
+        Ok(dataset)
+    }
+
+    /// Append rows from a file to an existing dataset
+    pub async fn append_to_dataset(
+        &self,
+        dataset_id: &str,
+        file_data: Vec<u8>,
+        filename: &str,
+    ) -> Result<DatasetUploadResponse, TinkerError> {
+        let part = reqwest::multipart::Part::bytes(file_data)
+            .file_name(filename.to_string())
+            .mime_str("application/octet-stream")
+            .map_err(|e| TinkerError::InvalidResponse(e.to_string()))?;
+
+        let form = reqwest::multipart::Form::new().part("file", part);
+
+        let response = self
+            .client
+            .post(format!("{}/v1/datasets/{}/append", self.base_url, dataset_id))
+            .header("Authorization", self.auth_header()?)
+            .multipart(form)
+            .send()
+            .await?;
+
+        let status = response.status();
+
+        if status == 401 {
+            return Err(TinkerError::Unauthorized);
+        }
+
+        if status == 404 {
+            return Err(TinkerError::NotFound(dataset_id.to_string()));
+        }
+
+        if !status.is_success() {
+            let error: ApiError = response
+                .json()
+                .await
+                .unwrap_or(ApiError {
+                    message: "Unknown error".to_string(),
+                    code: None,
+                });
+            return Err(TinkerError::ApiError {
+                status: status.as_u16(),
+                message: error.message,
+            });
+        }
+
+        let upload_response: DatasetUploadResponse = response
+            .json()
+            .await
+            .map_err(|e| TinkerError::InvalidResponse(e.to_string()))?;
+
+        Ok(upload_response)
+    }
+
     /// Test API connection
     pub async fn test_connection(&self) -> Result<bool, TinkerError> {
         let response = self
@@ -599,6 +815,17 @@ pub struct DatasetUploadResponse {
     pub row_count: u32,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatasetInfo {
+    pub dataset_id: String,
+    pub path: String,
+    pub size_bytes: u64,
+    pub row_count: u32,
+    /// Field names present in each row
+    #[serde(default)]
+    pub columns: Vec<String>,
+}
+
 impl Default for TinkerClient {
     fn default() -> Self {
         Self::new(None)
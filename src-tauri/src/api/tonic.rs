@@ -7,25 +7,42 @@
 
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use thiserror::Error;
 use uuid::Uuid;
 
+use crate::api::{code_suffix, ErrorBody, NetworkError};
+
 const BASE_URL: &str = "https://api.tonic.ai";
 
 #[derive(Error, Debug)]
 pub enum TonicError {
     #[error("API key not configured")]
     NoApiKey,
-    #[error("Request failed: {0}")]
-    RequestFailed(#[from] reqwest::Error),
+    #[error(transparent)]
+    RequestFailed(NetworkError),
     #[error("Invalid response: {0}")]
     InvalidResponse(String),
     #[error("Generation failed: {0}")]
     GenerationFailed(String),
-    #[error("API error: {status} - {message}")]
-    ApiError { status: u16, message: String },
+    #[error("API error: {status} - {message}{}", code_suffix(.code))]
+    ApiError {
+        status: u16,
+        message: String,
+        /// Machine-readable reason, e.g. "content_policy" or "quota_exceeded"
+        code: Option<String>,
+        details: Option<Value>,
+    },
     #[error("JSON parsing error: {0}")]
     JsonError(#[from] serde_json::Error),
+    #[error("Invalid schema: {0}")]
+    InvalidSchema(String),
+}
+
+impl From<reqwest::Error> for TonicError {
+    fn from(error: reqwest::Error) -> Self {
+        TonicError::RequestFailed(error.into())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,6 +55,9 @@ pub struct GenerationRequest {
     pub schema: Option<DataSchema>,
     /// Output format
     pub format: OutputFormat,
+    /// Seed for reproducible output, when the API honors it
+    #[serde(default)]
+    pub seed: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -45,6 +65,29 @@ pub struct DataSchema {
     pub fields: Vec<FieldDefinition>,
 }
 
+impl DataSchema {
+    /// Check the schema's structure before sending it to Tonic. Field types
+    /// are left for the API to judge
+    pub fn validate(&self) -> Result<(), TonicError> {
+        if self.fields.is_empty() {
+            return Err(TonicError::InvalidSchema("schema has no fields".to_string()));
+        }
+
+        let mut seen = std::collections::HashSet::new();
+        for field in &self.fields {
+            let name = field.name.trim();
+            if name.is_empty() {
+                return Err(TonicError::InvalidSchema("field name cannot be empty".to_string()));
+            }
+            if !seen.insert(name) {
+                return Err(TonicError::InvalidSchema(format!("duplicate field '{}'", name)));
+            }
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FieldDefinition {
     pub name: String,
@@ -65,6 +108,11 @@ pub enum OutputFormat {
 pub struct GenerationResult {
     /// Generated data as string (JSONL, CSV, or JSON)
     pub data: String,
+    /// Parsed records for JSON/JSONL output (`None` for CSV, or when nothing parsed)
+    pub records: Option<Vec<Value>>,
+    /// Lines of `data` (1-based) that couldn't be parsed, e.g. a truncated last record
+    #[serde(default)]
+    pub invalid_lines: Vec<u32>,
     /// Number of records generated
     pub record_count: u32,
     /// Generation metadata
@@ -76,6 +124,7 @@ pub struct GenerationMetadata {
     pub generation_id: String,
     pub duration_ms: u64,
     pub prompt_used: String,
+    pub seed: Option<u64>,
 }
 
 /// Training data format for fine-tuning
@@ -97,6 +146,8 @@ struct ApiGenerationRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     schema: Option<DataSchema>,
     output_format: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -105,6 +156,25 @@ struct ApiGenerationResponse {
     record_count: u32,
     generation_id: String,
     duration_ms: u64,
+    /// Explanation the API may attach (e.g. a content policy block), under
+    /// any of these names; kept as separate fields so a body carrying more
+    /// than one still parses
+    #[serde(default)]
+    message: Option<String>,
+    #[serde(default)]
+    reason: Option<String>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+impl ApiGenerationResponse {
+    /// First non-blank explanation the API attached
+    fn explanation(&self) -> Option<&str> {
+        [&self.message, &self.reason, &self.error]
+            .into_iter()
+            .filter_map(|text| text.as_deref())
+            .find(|text| !text.trim().is_empty())
+    }
 }
 
 pub struct TonicClient {
@@ -137,6 +207,9 @@ impl TonicClient {
     /// Generate synthetic data from a natural language prompt
     pub async fn generate(&self, request: GenerationRequest) -> Result<GenerationResult, TonicError> {
         let api_key = self.get_api_key()?;
+        if let Some(schema) = &request.schema {
+            schema.validate()?;
+        }
 
         let format_str = match request.format {
             OutputFormat::Jsonl => "jsonl",
@@ -149,6 +222,7 @@ impl TonicClient {
             num_records: request.num_records,
             schema: request.schema,
             output_format: format_str.to_string(),
+            seed: request.seed,
         };
 
         let response = self
@@ -163,10 +237,7 @@ impl TonicClient {
         let status = response.status();
         if !status.is_success() {
             let error_text = response.text().await.unwrap_or_default();
-            return Err(TonicError::ApiError {
-                status: status.as_u16(),
-                message: error_text,
-            });
+            return Err(api_error(status.as_u16(), &error_text));
         }
 
         let api_response: ApiGenerationResponse = response
@@ -174,13 +245,32 @@ impl TonicClient {
             .await
             .map_err(|e| TonicError::InvalidResponse(e.to_string()))?;
 
+        if api_response.record_count == 0 || api_response.data.trim().is_empty() {
+            let reason = api_response.explanation().unwrap_or(
+                "the API returned an empty result; try rephrasing the prompt",
+            );
+            return Err(TonicError::GenerationFailed(format!("no records produced: {}", reason)));
+        }
+
+        let (records, invalid_lines) = parse_records(&api_response.data, &request.format);
+        if !invalid_lines.is_empty() {
+            tracing::warn!(
+                "Generation {} returned {} unparsable line(s)",
+                api_response.generation_id,
+                invalid_lines.len()
+            );
+        }
+
         Ok(GenerationResult {
+            records,
+            invalid_lines,
             data: api_response.data,
             record_count: api_response.record_count,
             metadata: GenerationMetadata {
                 generation_id: api_response.generation_id,
                 duration_ms: api_response.duration_ms,
                 prompt_used: request.prompt,
+                seed: request.seed,
             },
         })
     }
@@ -192,60 +282,27 @@ impl TonicClient {
         domain: &str,
         num_examples: u32,
         style_hints: Option<&str>,
+        seed: Option<u64>,
     ) -> Result<Vec<TrainingExample>, TonicError> {
-        let prompt = format!(
-            r#"Generate {} high-quality training examples for fine-tuning a language model.
-
-Task: {}
-Domain: {}
-{}
-
-Each example should have:
-- "input": The user query or prompt
-- "output": The ideal assistant response
-- "system": Optional system prompt (include if relevant)
-
-Generate diverse, realistic examples that cover edge cases and common scenarios.
-Format as JSONL (one JSON object per line)."#,
-            num_examples,
-            task_description,
-            domain,
-            style_hints.map(|s| format!("Style: {}", s)).unwrap_or_default()
-        );
+        let prompt = training_data_prompt(task_description, domain, num_examples, style_hints);
 
         let request = GenerationRequest {
             prompt,
             num_records: num_examples,
-            schema: Some(DataSchema {
-                fields: vec![
-                    FieldDefinition {
-                        name: "input".to_string(),
-                        field_type: "string".to_string(),
-                        description: Some("User input or query".to_string()),
-                    },
-                    FieldDefinition {
-                        name: "output".to_string(),
-                        field_type: "string".to_string(),
-                        description: Some("Ideal assistant response".to_string()),
-                    },
-                    FieldDefinition {
-                        name: "system".to_string(),
-                        field_type: "string".to_string(),
-                        description: Some("Optional system prompt".to_string()),
-                    },
-                ],
-            }),
+            schema: Some(training_example_schema()),
             format: OutputFormat::Jsonl,
+            seed,
         };
 
         let result = self.generate(request).await?;
 
-        // Parse JSONL data into TrainingExamples
-        let examples: Vec<TrainingExample> = result
-            .data
-            .lines()
-            .filter(|line| !line.trim().is_empty())
-            .map(|line| serde_json::from_str(line))
+        let records = result.records.ok_or_else(|| {
+            TonicError::InvalidResponse("Generation returned no structured records".to_string())
+        })?;
+
+        let examples: Vec<TrainingExample> = records
+            .into_iter()
+            .map(serde_json::from_value)
             .collect::<Result<Vec<_>, _>>()
             .map_err(|e| TonicError::InvalidResponse(format!("Failed to parse training examples: {}", e)))?;
 
@@ -278,10 +335,7 @@ Format as JSONL (one JSON object per line)."#,
         let status = response.status();
         if !status.is_success() {
             let error_text = response.text().await.unwrap_or_default();
-            return Err(TonicError::ApiError {
-                status: status.as_u16(),
-                message: error_text,
-            });
+            return Err(api_error(status.as_u16(), &error_text));
         }
 
         let preview: GenerationPreview = response
@@ -292,6 +346,34 @@ Format as JSONL (one JSON object per line)."#,
         Ok(preview)
     }
 
+    /// Fetch the status of a generation job
+    pub async fn get_generation_status(
+        &self,
+        generation_id: &str,
+    ) -> Result<GenerationStatus, TonicError> {
+        let api_key = self.get_api_key()?;
+
+        let response = self
+            .client
+            .get(format!("{}/v1/fabricate/generate/{}", self.base_url, generation_id))
+            .header("Authorization", format!("Bearer {}", api_key))
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(api_error(status.as_u16(), &error_text));
+        }
+
+        let generation_status: GenerationStatus = response
+            .json()
+            .await
+            .map_err(|e| TonicError::InvalidResponse(e.to_string()))?;
+
+        Ok(generation_status)
+    }
+
     /// Test API connection
     pub async fn test_connection(&self) -> Result<bool, TonicError> {
         let api_key = self.get_api_key()?;
@@ -307,6 +389,91 @@ Format as JSONL (one JSON object per line)."#,
     }
 }
 
+/// Input/output/system schema used when generating training examples
+pub fn training_example_schema() -> DataSchema {
+    DataSchema {
+        fields: vec![
+            FieldDefinition {
+                name: "input".to_string(),
+                field_type: "string".to_string(),
+                description: Some("User input or query".to_string()),
+            },
+            FieldDefinition {
+                name: "output".to_string(),
+                field_type: "string".to_string(),
+                description: Some("Ideal assistant response".to_string()),
+            },
+            FieldDefinition {
+                name: "system".to_string(),
+                field_type: "string".to_string(),
+                description: Some("Optional system prompt".to_string()),
+            },
+        ],
+    }
+}
+
+/// Prompt used to generate fine-tuning examples for a task
+pub fn training_data_prompt(
+    task_description: &str,
+    domain: &str,
+    num_examples: u32,
+    style_hints: Option<&str>,
+) -> String {
+    format!(
+        r#"Generate {} high-quality training examples for fine-tuning a language model.
+
+Task: {}
+Domain: {}
+{}
+
+Each example should have:
+- "input": The user query or prompt
+- "output": The ideal assistant response
+- "system": Optional system prompt (include if relevant)
+
+Generate diverse, realistic examples that cover edge cases and common scenarios.
+Format as JSONL (one JSON object per line)."#,
+        num_examples,
+        task_description,
+        domain,
+        style_hints.map(|s| format!("Style: {}", s)).unwrap_or_default()
+    )
+}
+
+/// Parse generated JSON/JSONL data into individual records, keeping the ones
+/// that parse and returning the line numbers of those that don't
+///
+/// Records are `None` for CSV or when nothing parsed; the raw data is still
+/// returned to the caller either way.
+fn parse_records(data: &str, format: &OutputFormat) -> (Option<Vec<Value>>, Vec<u32>) {
+    let mut invalid_lines = Vec::new();
+    let records = match format {
+        OutputFormat::Csv => return (None, invalid_lines),
+        OutputFormat::Jsonl => data
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .filter_map(|(index, line)| match serde_json::from_str::<Value>(line) {
+                Ok(record) => Some(record),
+                Err(_) => {
+                    invalid_lines.push(index as u32 + 1);
+                    None
+                }
+            })
+            .collect::<Vec<_>>(),
+        OutputFormat::Json => match serde_json::from_str::<Value>(data) {
+            Ok(Value::Array(items)) => items,
+            Ok(other) => vec![other],
+            Err(e) => {
+                invalid_lines.push(e.line() as u32);
+                Vec::new()
+            }
+        },
+    };
+
+    ((!records.is_empty()).then_some(records), invalid_lines)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GenerationPreview {
     pub estimated_tokens: u32,
@@ -315,8 +482,55 @@ pub struct GenerationPreview {
     pub schema_inferred: Option<DataSchema>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenerationStatus {
+    pub status: String, // "pending", "running", "completed", "failed"
+    /// Completion reported by the API as a 0-1 fraction
+    #[serde(default)]
+    pub progress: Option<f64>,
+    /// Completion reported by the API as a 0-100 percentage
+    #[serde(default)]
+    pub percent_complete: Option<f64>,
+    #[serde(default, alias = "records_generated")]
+    pub records_completed: Option<u32>,
+    #[serde(default, alias = "num_records")]
+    pub records_requested: Option<u32>,
+}
+
+impl GenerationStatus {
+    pub fn is_finished(&self) -> bool {
+        matches!(self.status.as_str(), "completed" | "failed" | "cancelled")
+    }
+
+    /// Completion fraction from the API's own fields, if it reports any
+    pub fn fraction(&self) -> Option<f64> {
+        // The field the value arrived in decides its scale, so 1% isn't read as done
+        if let Some(progress) = self.progress {
+            return Some(progress.clamp(0.0, 1.0));
+        }
+        if let Some(percent) = self.percent_complete {
+            return Some((percent / 100.0).clamp(0.0, 1.0));
+        }
+        match (self.records_completed, self.records_requested) {
+            (Some(done), Some(total)) if total > 0 => Some((done as f64 / total as f64).min(1.0)),
+            _ => None,
+        }
+    }
+}
+
 impl Default for TonicClient {
     fn default() -> Self {
         Self::new(None)
     }
 }
+
+/// Map a non-2xx response to an error, keeping any structured fields from the body
+fn api_error(status: u16, body: &str) -> TonicError {
+    let body = ErrorBody::parse(body);
+    TonicError::ApiError {
+        status,
+        message: body.message,
+        code: body.code,
+        details: body.details,
+    }
+}
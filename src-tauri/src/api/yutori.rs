@@ -6,27 +6,97 @@
 //! - POST /v1/research - Deep web research
 //! - GET /v1/research/{id} - Get research status/results
 
+use std::sync::Arc;
+
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use thiserror::Error;
+use tokio::sync::Semaphore;
 use uuid::Uuid;
 
+use crate::api::{code_suffix, ErrorBody, NetworkError};
+
 const BASE_URL: &str = "https://api.yutori.com";
 
 #[derive(Error, Debug)]
 pub enum YutoriError {
     #[error("API key not configured")]
     NoApiKey,
-    #[error("Request failed: {0}")]
-    RequestFailed(#[from] reqwest::Error),
+    #[error(transparent)]
+    RequestFailed(NetworkError),
     #[error("Invalid response: {0}")]
     InvalidResponse(String),
     #[error("Research failed: {0}")]
     ResearchFailed(String),
-    #[error("API error: {status} - {message}")]
-    ApiError { status: u16, message: String },
+    #[error("API error: {status} - {message}{}", code_suffix(.code))]
+    ApiError {
+        status: u16,
+        message: String,
+        /// Machine-readable reason, e.g. "content_policy" or "quota_exceeded"
+        code: Option<String>,
+        details: Option<Value>,
+    },
     #[error("Research still in progress")]
     InProgress { research_id: String },
+    #[error("Research timed out")]
+    TimedOut { research_id: String },
+}
+
+impl From<reqwest::Error> for YutoriError {
+    fn from(error: reqwest::Error) -> Self {
+        YutoriError::RequestFailed(error.into())
+    }
+}
+
+/// Backoff schedule used while polling a research task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PollConfig {
+    pub initial_delay_ms: u64,
+    pub max_delay_ms: u64,
+    pub max_attempts: u32,
+    /// Random spread applied to each delay, as a fraction (0.2 = +/-20%)
+    #[serde(default = "default_jitter")]
+    pub jitter: f64,
+    /// Polls allowed in flight at once across all research tasks
+    #[serde(default = "default_max_concurrent_polls")]
+    pub max_concurrent_polls: usize,
+}
+
+fn default_jitter() -> f64 {
+    0.2
+}
+
+fn default_max_concurrent_polls() -> usize {
+    2
+}
+
+impl PollConfig {
+    /// Delay before the poll following one that waited `delay_ms`
+    pub fn next_delay(&self, delay_ms: u64) -> u64 {
+        (delay_ms * 2).min(self.max_delay_ms)
+    }
+
+    /// Spread `delay_ms` randomly by `jitter` so concurrent polls don't line up
+    pub fn jittered(&self, delay_ms: u64) -> u64 {
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        // Uniform in [-1, 1), drawn from a v4 UUID's random bits
+        let unit = (Uuid::new_v4().as_u128() as u64 >> 11) as f64 / (1u64 << 53) as f64;
+        let factor = 1.0 + jitter * (unit * 2.0 - 1.0);
+        (delay_ms as f64 * factor).round() as u64
+    }
+}
+
+impl Default for PollConfig {
+    fn default() -> Self {
+        Self {
+            initial_delay_ms: 1000,
+            max_delay_ms: 10000,
+            max_attempts: 60, // Max ~10 minutes of polling
+            jitter: default_jitter(),
+            max_concurrent_polls: default_max_concurrent_polls(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -138,14 +208,20 @@ pub struct YutoriClient {
     client: Client,
     api_key: Option<String>,
     base_url: String,
+    poll_config: PollConfig,
+    /// Shared by every polling loop to cap concurrent status requests
+    poll_limiter: Arc<Semaphore>,
 }
 
 impl YutoriClient {
     pub fn new(api_key: Option<String>) -> Self {
+        let poll_config = PollConfig::default();
         Self {
             client: Client::new(),
             api_key,
             base_url: BASE_URL.to_string(),
+            poll_limiter: Arc::new(Semaphore::new(poll_config.max_concurrent_polls.max(1))),
+            poll_config,
         }
     }
 
@@ -161,6 +237,18 @@ impl YutoriClient {
         self.api_key.as_deref().ok_or(YutoriError::NoApiKey)
     }
 
+    pub fn poll_config(&self) -> PollConfig {
+        self.poll_config.clone()
+    }
+
+    /// Replace the polling schedule, resizing the concurrency limit to match
+    ///
+    /// Pollers already running keep the limit they started with.
+    pub fn set_poll_config(&mut self, config: PollConfig) {
+        self.poll_limiter = Arc::new(Semaphore::new(config.max_concurrent_polls.max(1)));
+        self.poll_config = config;
+    }
+
     /// Start a research task (returns immediately with research_id)
     pub async fn start_research(&self, request: ResearchRequest) -> Result<String, YutoriError> {
         let api_key = self.get_api_key()?;
@@ -184,10 +272,7 @@ impl YutoriClient {
         let status = response.status();
         if !status.is_success() {
             let error_text = response.text().await.unwrap_or_default();
-            return Err(YutoriError::ApiError {
-                status: status.as_u16(),
-                message: error_text,
-            });
+            return Err(api_error(status.as_u16(), &error_text));
         }
 
         let api_response: ApiResearchResponse = response
@@ -201,84 +286,38 @@ impl YutoriClient {
     /// Get research results (poll until complete)
     pub async fn get_research(&self, research_id: &str) -> Result<ResearchResult, YutoriError> {
         let api_key = self.get_api_key()?;
-
-        let response = self
-            .client
-            .get(format!("{}/v1/research/{}", self.base_url, research_id))
-            .header("Authorization", format!("Bearer {}", api_key))
-            .send()
-            .await?;
-
-        let status = response.status();
-        if !status.is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(YutoriError::ApiError {
-                status: status.as_u16(),
-                message: error_text,
-            });
-        }
-
-        let api_response: ApiResearchResponse = response
-            .json()
-            .await
-            .map_err(|e| YutoriError::InvalidResponse(e.to_string()))?;
-
-        match api_response.status {
-            ResearchStatus::Completed => Ok(ResearchResult {
-                summary: api_response.summary.unwrap_or_default(),
-                insights: api_response.insights,
-                sources: api_response.sources,
-                raw_findings: api_response.findings,
-                metadata: ResearchMetadata {
-                    research_id: api_response.research_id,
-                    duration_ms: api_response.duration_ms,
-                    sources_consulted: api_response.sources_consulted,
-                    status: ResearchStatus::Completed,
-                },
-            }),
-            ResearchStatus::Failed => Err(YutoriError::ResearchFailed(
-                api_response.summary.unwrap_or_else(|| "Research failed".to_string()),
-            )),
-            _ => Err(YutoriError::InProgress {
-                research_id: api_response.research_id,
-            }),
-        }
+        fetch_research(&self.client, &self.base_url, api_key, research_id).await
     }
 
     /// Perform deep web research on a topic (blocking - waits for completion)
     pub async fn research(&self, request: ResearchRequest) -> Result<ResearchResult, YutoriError> {
         let research_id = self.start_research(request).await?;
+        self.wait_for_research(&research_id).await
+    }
 
-        // Poll for results with exponential backoff
-        let mut delay_ms = 1000u64;
-        let max_delay_ms = 10000u64;
-        let max_attempts = 60; // Max ~10 minutes of polling
-
-        for _ in 0..max_attempts {
-            tokio::time::sleep(tokio::time::Duration::from_millis(delay_ms)).await;
-
-            match self.get_research(&research_id).await {
-                Ok(result) => return Ok(result),
-                Err(YutoriError::InProgress { .. }) => {
-                    delay_ms = (delay_ms * 2).min(max_delay_ms);
-                    continue;
-                }
-                Err(e) => return Err(e),
-            }
-        }
+    /// Poll an already-started research task until it completes
+    pub async fn wait_for_research(&self, research_id: &str) -> Result<ResearchResult, YutoriError> {
+        self.poller()?.wait(research_id).await
+    }
 
-        Err(YutoriError::ResearchFailed(
-            "Research timed out".to_string(),
-        ))
+    /// A handle for polling research without borrowing the client, so callers
+    /// can release the client's lock while a task runs
+    pub fn poller(&self) -> Result<ResearchPoller, YutoriError> {
+        Ok(ResearchPoller {
+            client: self.client.clone(),
+            api_key: self.get_api_key()?.to_string(),
+            base_url: self.base_url.clone(),
+            config: self.poll_config.clone(),
+            limiter: self.poll_limiter.clone(),
+        })
     }
 
-    /// Research ML training best practices for a specific task
-    pub async fn research_ml_task(
-        &self,
+    /// Build the research request used for ML training best practices
+    pub fn ml_research_request(
         task_description: &str,
         model_type: &str,
         training_type: &str,
-    ) -> Result<MLResearchResult, YutoriError> {
+    ) -> ResearchRequest {
         let query = format!(
             "Best practices and recommended hyperparameters for {} fine-tuning {} models. \
             Task: {}. \
@@ -287,50 +326,24 @@ impl YutoriClient {
             training_type, model_type, task_description
         );
 
-        let request = ResearchRequest {
+        ResearchRequest {
             query,
             depth: 4,
             domain: Some("machine learning fine-tuning".to_string()),
             max_sources: Some(20),
-        };
+        }
+    }
 
+    /// Research ML training best practices for a specific task
+    pub async fn research_ml_task(
+        &self,
+        task_description: &str,
+        model_type: &str,
+        training_type: &str,
+    ) -> Result<MLResearchResult, YutoriError> {
+        let request = Self::ml_research_request(task_description, model_type, training_type);
         let result = self.research(request).await?;
-
-        // Parse the research results into structured ML recommendations
-        // This is a simplified parsing - in production, you'd use Claude to structure this
-        let ml_result = MLResearchResult {
-            recommended_params: result
-                .insights
-                .iter()
-                .filter(|i| i.contains("rate") || i.contains("batch") || i.contains("rank"))
-                .take(5)
-                .map(|insight| ParameterRecommendation {
-                    name: extract_param_name(insight),
-                    value: extract_param_value(insight),
-                    rationale: insight.clone(),
-                })
-                .collect(),
-            best_practices: result
-                .insights
-                .iter()
-                .filter(|i| i.contains("should") || i.contains("best") || i.contains("recommend"))
-                .cloned()
-                .collect(),
-            data_patterns: result
-                .insights
-                .iter()
-                .filter(|i| i.contains("format") || i.contains("data") || i.contains("example"))
-                .cloned()
-                .collect(),
-            pitfalls: result
-                .insights
-                .iter()
-                .filter(|i| i.contains("avoid") || i.contains("don't") || i.contains("warning"))
-                .cloned()
-                .collect(),
-        };
-
-        Ok(ml_result)
+        Ok(summarize_ml_research(&result))
     }
 
     /// Test API connection
@@ -348,6 +361,132 @@ impl YutoriClient {
     }
 }
 
+/// Polls research tasks with the backoff and concurrency limit of the client it came from
+#[derive(Clone)]
+pub struct ResearchPoller {
+    client: Client,
+    api_key: String,
+    base_url: String,
+    config: PollConfig,
+    limiter: Arc<Semaphore>,
+}
+
+impl ResearchPoller {
+    /// Poll until the research completes, fails or runs out of attempts
+    pub async fn wait(&self, research_id: &str) -> Result<ResearchResult, YutoriError> {
+        // Poll for results with exponential backoff
+        let mut delay_ms = self.config.initial_delay_ms;
+
+        for _ in 0..self.config.max_attempts {
+            let jittered = self.config.jittered(delay_ms);
+            tokio::time::sleep(tokio::time::Duration::from_millis(jittered)).await;
+
+            let result = {
+                let _permit = self
+                    .limiter
+                    .acquire()
+                    .await
+                    .map_err(|e| YutoriError::InvalidResponse(e.to_string()))?;
+                fetch_research(&self.client, &self.base_url, &self.api_key, research_id).await
+            };
+
+            match result {
+                Ok(result) => return Ok(result),
+                Err(YutoriError::InProgress { .. }) => {
+                    delay_ms = self.config.next_delay(delay_ms);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(YutoriError::TimedOut {
+            research_id: research_id.to_string(),
+        })
+    }
+}
+
+async fn fetch_research(
+    client: &Client,
+    base_url: &str,
+    api_key: &str,
+    research_id: &str,
+) -> Result<ResearchResult, YutoriError> {
+    let response = client
+        .get(format!("{}/v1/research/{}", base_url, research_id))
+        .header("Authorization", format!("Bearer {}", api_key))
+        .send()
+        .await?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(api_error(status.as_u16(), &error_text));
+    }
+
+    let api_response: ApiResearchResponse = response
+        .json()
+        .await
+        .map_err(|e| YutoriError::InvalidResponse(e.to_string()))?;
+
+    match api_response.status {
+        ResearchStatus::Completed => Ok(ResearchResult {
+            summary: api_response.summary.unwrap_or_default(),
+            insights: api_response.insights,
+            sources: api_response.sources,
+            raw_findings: api_response.findings,
+            metadata: ResearchMetadata {
+                research_id: api_response.research_id,
+                duration_ms: api_response.duration_ms,
+                sources_consulted: api_response.sources_consulted,
+                status: ResearchStatus::Completed,
+            },
+        }),
+        ResearchStatus::Failed => Err(YutoriError::ResearchFailed(
+            api_response.summary.unwrap_or_else(|| "Research failed".to_string()),
+        )),
+        _ => Err(YutoriError::InProgress {
+            research_id: api_response.research_id,
+        }),
+    }
+}
+
+/// Structure raw research results into ML recommendations
+pub fn summarize_ml_research(result: &ResearchResult) -> MLResearchResult {
+    // This is a simplified parsing - in production, you'd use Claude to structure this
+    MLResearchResult {
+        recommended_params: result
+            .insights
+            .iter()
+            .filter(|i| i.contains("rate") || i.contains("batch") || i.contains("rank"))
+            .take(5)
+            .map(|insight| ParameterRecommendation {
+                name: extract_param_name(insight),
+                value: extract_param_value(insight),
+                rationale: insight.clone(),
+            })
+            .collect(),
+        best_practices: result
+            .insights
+            .iter()
+            .filter(|i| i.contains("should") || i.contains("best") || i.contains("recommend"))
+            .cloned()
+            .collect(),
+        data_patterns: result
+            .insights
+            .iter()
+            .filter(|i| i.contains("format") || i.contains("data") || i.contains("example"))
+            .cloned()
+            .collect(),
+        pitfalls: result
+            .insights
+            .iter()
+            .filter(|i| i.contains("avoid") || i.contains("don't") || i.contains("warning"))
+            .cloned()
+            .collect(),
+    }
+}
+
 /// Helper to extract parameter name from insight text
 fn extract_param_name(insight: &str) -> String {
     if insight.to_lowercase().contains("learning rate") {
@@ -381,3 +520,14 @@ impl Default for YutoriClient {
         Self::new(None)
     }
 }
+
+/// Map a non-2xx response to an error, keeping any structured fields from the body
+fn api_error(status: u16, body: &str) -> YutoriError {
+    let body = ErrorBody::parse(body);
+    YutoriError::ApiError {
+        status,
+        message: body.message,
+        code: body.code,
+        details: body.details,
+    }
+}
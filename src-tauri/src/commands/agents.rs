@@ -2,10 +2,19 @@
 //!
 //! SESSION 2: Implement these commands
 
-use tauri::State;
-use crate::state::AppState;
-use crate::api::anthropic::AgentType;
+use std::collections::{HashMap, HashSet};
+
+use futures::stream::{self, StreamExt};
+use tauri::{AppHandle, Emitter, State};
+use tauri_plugin_store::StoreExt;
+use crate::state::{AgentUsage, AppState, STORE_FILE};
+use crate::api::anthropic::{self, AgentSettings, AgentType, AnthropicClient, Message};
+use crate::api::tinker::TrainingType;
+use crate::commands::data::{normalize_text, TrainingExample};
+use crate::commands::research::ResearchResponse;
+use crate::commands::training::merge_json;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 // ============ Intent Parsing ============
 
@@ -36,6 +45,32 @@ pub struct TrainingIntent {
 pub async fn parse_intent(
     state: State<'_, AppState>,
     transcript: String,
+) -> Result<TrainingIntent, String> {
+    let result = intent_from_transcript(&state, transcript).await;
+    state.capture("parse_intent", result).await
+}
+
+/// Run a saved transcript (e.g. from `export_transcripts`) through intent
+/// parsing without recording audio
+#[tauri::command]
+pub async fn replay_transcript(
+    state: State<'_, AppState>,
+    text: String,
+) -> Result<TrainingIntent, String> {
+    let result = replay_transcript_inner(&state, text).await;
+    state.capture("replay_transcript", result).await
+}
+
+async fn replay_transcript_inner(state: &AppState, text: String) -> Result<TrainingIntent, String> {
+    if text.trim().is_empty() {
+        return Err("Transcript is empty".to_string());
+    }
+    intent_from_transcript(state, text).await
+}
+
+async fn intent_from_transcript(
+    state: &AppState,
+    transcript: String,
 ) -> Result<TrainingIntent, String> {
     let client = state.anthropic.lock().await;
 
@@ -43,10 +78,12 @@ pub async fn parse_intent(
         .chat_with_agent(AgentType::Intent, &transcript)
         .await
         .map_err(|e| e.to_string())?;
+    state.record_usage(AgentType::Intent, response.usage.as_ref()).await;
+    response.ensure_complete().map_err(|e| e.to_string())?;
 
     // TODO: Parse the response into TrainingIntent
     // For now, return a placeholder
-    Ok(TrainingIntent {
+    let intent = TrainingIntent {
         task_description: transcript.clone(),
         domain: "general".to_string(),
         style: None,
@@ -56,7 +93,19 @@ pub async fn parse_intent(
         suggested_example_count: Some(1000),
         constraints: vec![],
         confidence: 0.8,
-    })
+    };
+
+    Ok(normalize_intent(intent))
+}
+
+/// Canonicalize loosely-phrased intent fields (e.g. "supervised" -> "sft")
+fn normalize_intent(mut intent: TrainingIntent) -> TrainingIntent {
+    intent.suggested_training_type = intent.suggested_training_type.map(|t| {
+        TrainingType::from_alias(&t)
+            .map(|canonical| canonical.as_str().to_string())
+            .unwrap_or(t)
+    });
+    intent
 }
 
 // ============ Data Validation ============
@@ -105,6 +154,15 @@ pub async fn validate_data(
     state: State<'_, AppState>,
     data_json: String,
     intent: TrainingIntent,
+) -> Result<ValidationReport, String> {
+    let result = validate_data_inner(&state, data_json, intent).await;
+    state.capture("validate_data", result).await
+}
+
+async fn validate_data_inner(
+    state: &AppState,
+    data_json: String,
+    intent: TrainingIntent,
 ) -> Result<ValidationReport, String> {
     let client = state.anthropic.lock().await;
 
@@ -117,6 +175,8 @@ pub async fn validate_data(
         .chat_with_agent(AgentType::Validation, &prompt)
         .await
         .map_err(|e| e.to_string())?;
+    state.record_usage(AgentType::Validation, response.usage.as_ref()).await;
+    response.ensure_complete().map_err(|e| e.to_string())?;
 
     // TODO: Parse the response into ValidationReport
     // For now, return a placeholder
@@ -129,6 +189,340 @@ pub async fn validate_data(
     })
 }
 
+/// Examples sent to the Validation agent per request
+const VALIDATION_BATCH_SIZE: usize = 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartialValidationReport {
+    /// Dataset indices this report covers, for merging into a full report
+    pub indices: Vec<u32>,
+    pub report: ValidationReport,
+}
+
+/// Re-validate only the given examples (e.g. ones fixed after a failed validation)
+#[tauri::command]
+pub async fn revalidate(
+    state: State<'_, AppState>,
+    examples: Vec<TrainingExample>,
+    indices: Vec<u32>,
+) -> Result<PartialValidationReport, String> {
+    let result = revalidate_inner(&state, examples, indices).await;
+    state.capture("revalidate", result).await
+}
+
+async fn revalidate_inner(
+    state: &AppState,
+    examples: Vec<TrainingExample>,
+    indices: Vec<u32>,
+) -> Result<PartialValidationReport, String> {
+    let mut indices = indices;
+    indices.sort_unstable();
+    indices.dedup();
+    if indices.is_empty() {
+        return Err("No examples selected for revalidation".to_string());
+    }
+    if let Some(&bad) = indices.iter().find(|&&i| i as usize >= examples.len()) {
+        return Err(format!("Index {} is out of range ({} examples)", bad, examples.len()));
+    }
+
+    let client = state.anthropic.lock().await;
+    let report = validate_indices(state, &client, &examples, &indices).await?;
+
+    Ok(PartialValidationReport { indices, report })
+}
+
+/// Run the given (sorted, in-range) examples through the Validation agent in batches
+async fn validate_indices(
+    state: &AppState,
+    client: &AnthropicClient,
+    examples: &[TrainingExample],
+    indices: &[u32],
+) -> Result<ValidationReport, String> {
+    let mut issues = Vec::new();
+    let mut suggestions: Vec<String> = Vec::new();
+    let mut valid_samples = 0u32;
+    let mut all_valid = true;
+
+    for batch in indices.chunks(VALIDATION_BATCH_SIZE) {
+        // Tag each example with its dataset index so issue locations map back
+        let samples: Vec<Value> = batch
+            .iter()
+            .map(|&i| {
+                let example = &examples[i as usize];
+                serde_json::json!({
+                    "index": i,
+                    "input": example.input,
+                    "output": example.output,
+                    "system": example.system,
+                })
+            })
+            .collect();
+        let samples_json = serde_json::to_string_pretty(&samples).map_err(|e| e.to_string())?;
+
+        let (result, usage) = client
+            .validate_data(&samples_json)
+            .await
+            .map_err(|e| e.to_string())?;
+        state.record_usage(AgentType::Validation, usage.as_ref()).await;
+
+        all_valid &= result.valid;
+        valid_samples += result.stats["valid_samples"]
+            .as_u64()
+            .map(|n| n as u32)
+            .unwrap_or(if result.valid { batch.len() as u32 } else { 0 });
+
+        issues.extend(result.issues.into_iter().map(|issue| ValidationIssue {
+            severity: match issue.severity.as_str() {
+                "error" => IssueSeverity::Error,
+                "warning" => IssueSeverity::Warning,
+                _ => IssueSeverity::Info,
+            },
+            category: issue.location.unwrap_or_else(|| "general".to_string()),
+            description: issue.message,
+            affected_count: None,
+        }));
+        for recommendation in result.recommendations {
+            if !suggestions.contains(&recommendation) {
+                suggestions.push(recommendation);
+            }
+        }
+    }
+
+    let quality_score = (valid_samples.min(indices.len() as u32) * 100) / indices.len() as u32;
+
+    Ok(ValidationReport {
+        quality_score,
+        is_acceptable: all_valid,
+        issues,
+        suggestions,
+        sample_analysis: vec![],
+    })
+}
+
+// ============ Dataset Ranking ============
+
+/// Datasets validated at once when ranking
+const MAX_CONCURRENT_VALIDATIONS: usize = 3;
+/// Examples per dataset sent to the Validation agent when ranking
+const RANKING_SAMPLE_SIZE: usize = 40;
+/// Issues reported per ranked dataset
+const MAX_KEY_ISSUES: usize = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValidationMethod {
+    Llm,
+    Heuristic,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatasetRanking {
+    pub dataset_id: String,
+    /// 1 for the best dataset
+    pub rank: u32,
+    pub quality_score: u32,
+    pub is_acceptable: bool,
+    pub example_count: u32,
+    pub method: ValidationMethod,
+    /// Most severe issues found, errors first
+    pub key_issues: Vec<ValidationIssue>,
+    /// Why the dataset was scored heuristically although LLM validation was requested
+    pub fallback_reason: Option<String>,
+}
+
+/// Validate several candidate datasets and rank them by quality score.
+/// Uses the Validation agent on a sample of each when an Anthropic key is
+/// configured, falling back to heuristic checks otherwise. LLM and heuristic
+/// scores aren't comparable, so if LLM validation fails for any dataset, all
+/// of them are ranked heuristically.
+#[tauri::command]
+pub async fn rank_datasets(
+    state: State<'_, AppState>,
+    datasets: Vec<(String, Vec<TrainingExample>)>,
+    use_llm: Option<bool>,
+) -> Result<Vec<DatasetRanking>, String> {
+    let result = rank_datasets_inner(&state, datasets, use_llm).await;
+    state.capture("rank_datasets", result).await
+}
+
+async fn rank_datasets_inner(
+    state: &AppState,
+    datasets: Vec<(String, Vec<TrainingExample>)>,
+    use_llm: Option<bool>,
+) -> Result<Vec<DatasetRanking>, String> {
+    if datasets.is_empty() {
+        return Err("No datasets to rank".to_string());
+    }
+    let mut seen = HashSet::new();
+    if let Some((id, _)) = datasets.iter().find(|(id, _)| !seen.insert(id.as_str())) {
+        return Err(format!("Dataset '{}' was given more than once", id));
+    }
+
+    let client = state.anthropic.lock().await;
+    let use_llm = use_llm.unwrap_or(true) && client.has_api_key();
+
+    let mut scored: Vec<(DatasetRanking, Vec<TrainingExample>)> = stream::iter(datasets)
+        .map(|(dataset_id, examples)| {
+            let state = &*state;
+            let client = &client;
+            async move {
+                let (report, method, fallback_reason) = if !use_llm || examples.is_empty() {
+                    (heuristic_report(&examples), ValidationMethod::Heuristic, None)
+                } else {
+                    let indices = sample_indices(examples.len(), RANKING_SAMPLE_SIZE);
+                    match validate_indices(state, client, &examples, &indices).await {
+                        Ok(report) => (report, ValidationMethod::Llm, None),
+                        Err(e) => {
+                            (heuristic_report(&examples), ValidationMethod::Heuristic, Some(e))
+                        }
+                    }
+                };
+
+                let ranking =
+                    dataset_ranking(dataset_id, &examples, report, method, fallback_reason);
+                (ranking, examples)
+            }
+        })
+        .buffer_unordered(MAX_CONCURRENT_VALIDATIONS)
+        .collect()
+        .await;
+
+    if scored.iter().any(|(ranking, _)| ranking.fallback_reason.is_some()) {
+        for (ranking, examples) in &mut scored {
+            if matches!(ranking.method, ValidationMethod::Llm) {
+                *ranking = dataset_ranking(
+                    std::mem::take(&mut ranking.dataset_id),
+                    examples,
+                    heuristic_report(examples),
+                    ValidationMethod::Heuristic,
+                    Some("Another dataset could not be validated by the LLM".to_string()),
+                );
+            }
+        }
+    }
+    let mut rankings: Vec<DatasetRanking> = scored.into_iter().map(|(r, _)| r).collect();
+
+    rankings.sort_by(|a, b| {
+        b.quality_score
+            .cmp(&a.quality_score)
+            .then(b.is_acceptable.cmp(&a.is_acceptable))
+            .then_with(|| a.dataset_id.cmp(&b.dataset_id))
+    });
+    for (i, ranking) in rankings.iter_mut().enumerate() {
+        ranking.rank = i as u32 + 1;
+    }
+
+    Ok(rankings)
+}
+
+fn dataset_ranking(
+    dataset_id: String,
+    examples: &[TrainingExample],
+    report: ValidationReport,
+    method: ValidationMethod,
+    fallback_reason: Option<String>,
+) -> DatasetRanking {
+    let mut key_issues = report.issues;
+    key_issues.sort_by_key(|issue| severity_rank(&issue.severity));
+    key_issues.truncate(MAX_KEY_ISSUES);
+
+    DatasetRanking {
+        dataset_id,
+        rank: 0,
+        quality_score: report.quality_score,
+        is_acceptable: report.is_acceptable,
+        example_count: examples.len() as u32,
+        method,
+        key_issues,
+        fallback_reason,
+    }
+}
+
+/// Up to `count` indices spread evenly across `len` examples
+fn sample_indices(len: usize, count: usize) -> Vec<u32> {
+    if len <= count {
+        return (0..len as u32).collect();
+    }
+    (0..count).map(|i| (i * len / count) as u32).collect()
+}
+
+fn severity_rank(severity: &IssueSeverity) -> u8 {
+    match severity {
+        IssueSeverity::Error => 0,
+        IssueSeverity::Warning => 1,
+        IssueSeverity::Info => 2,
+    }
+}
+
+/// Score a dataset without an LLM: the share of examples that are non-empty
+/// and not duplicates of an earlier example
+fn heuristic_report(examples: &[TrainingExample]) -> ValidationReport {
+    if examples.is_empty() {
+        return ValidationReport {
+            quality_score: 0,
+            is_acceptable: false,
+            issues: vec![ValidationIssue {
+                severity: IssueSeverity::Error,
+                category: "size".to_string(),
+                description: "Dataset has no examples".to_string(),
+                affected_count: None,
+            }],
+            suggestions: vec![],
+            sample_analysis: vec![],
+        };
+    }
+
+    let mut seen = HashSet::new();
+    let (mut empty, mut duplicates, mut short) = (0u32, 0u32, 0u32);
+    for example in examples {
+        if example.input.trim().is_empty() || example.output.trim().is_empty() {
+            empty += 1;
+        } else if !seen.insert((normalize_text(&example.input), normalize_text(&example.output))) {
+            duplicates += 1;
+        } else if example.output.split_whitespace().count() < 3 {
+            short += 1;
+        }
+    }
+
+    let total = examples.len() as u32;
+    let mut issues = Vec::new();
+    let mut suggestions = Vec::new();
+    if empty > 0 {
+        issues.push(ValidationIssue {
+            severity: IssueSeverity::Error,
+            category: "completeness".to_string(),
+            description: format!("{} example(s) have an empty input or output", empty),
+            affected_count: Some(empty),
+        });
+        suggestions.push("Remove or fill in empty examples".to_string());
+    }
+    if duplicates > 0 {
+        issues.push(ValidationIssue {
+            severity: IssueSeverity::Warning,
+            category: "diversity".to_string(),
+            description: format!("{} example(s) duplicate an earlier example", duplicates),
+            affected_count: Some(duplicates),
+        });
+        suggestions.push("Deduplicate the dataset".to_string());
+    }
+    if short > 0 {
+        issues.push(ValidationIssue {
+            severity: IssueSeverity::Info,
+            category: "length".to_string(),
+            description: format!("{} example(s) have outputs under three words", short),
+            affected_count: Some(short),
+        });
+    }
+
+    ValidationReport {
+        quality_score: (total - empty - duplicates) * 100 / total,
+        is_acceptable: empty == 0,
+        issues,
+        suggestions,
+        sample_analysis: vec![],
+    }
+}
+
 // ============ Config Recommendation ============
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -170,6 +564,15 @@ pub async fn recommend_config(
     state: State<'_, AppState>,
     intent: TrainingIntent,
     data_stats: DataStats,
+) -> Result<ConfigRecommendation, String> {
+    let result = recommend_config_inner(&state, intent, data_stats).await;
+    state.capture("recommend_config", result).await
+}
+
+async fn recommend_config_inner(
+    state: &AppState,
+    intent: TrainingIntent,
+    data_stats: DataStats,
 ) -> Result<ConfigRecommendation, String> {
     let client = state.anthropic.lock().await;
 
@@ -182,6 +585,8 @@ pub async fn recommend_config(
         .chat_with_agent(AgentType::Config, &prompt)
         .await
         .map_err(|e| e.to_string())?;
+    state.record_usage(AgentType::Config, response.usage.as_ref()).await;
+    response.ensure_complete().map_err(|e| e.to_string())?;
 
     // TODO: Parse the response into ConfigRecommendation
     // For now, return a placeholder
@@ -213,37 +618,658 @@ pub struct DataStats {
     pub min_tokens: u32,
 }
 
+// ============ Config Refinement ============
+
+/// Messages kept from earlier refinement turns (older turns are dropped)
+const MAX_REFINEMENT_HISTORY: usize = 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigChange {
+    /// Dotted path of the changed field, e.g. `hyperparameters.num_epochs`
+    pub field: String,
+    pub before: Value,
+    pub after: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigRefinement {
+    pub config: ConfigRecommendation,
+    pub changes: Vec<ConfigChange>,
+}
+
+/// Adjust a config from natural-language feedback, keeping the conversation
+/// with the Config agent across calls (pass `reset` to start over)
+#[tauri::command]
+pub async fn refine_config(
+    state: State<'_, AppState>,
+    current_config: ConfigRecommendation,
+    feedback: String,
+    data_stats: DataStats,
+    reset: Option<bool>,
+) -> Result<ConfigRefinement, String> {
+    let result = refine_config_inner(&state, current_config, feedback, data_stats, reset).await;
+    state.capture("refine_config", result).await
+}
+
+async fn refine_config_inner(
+    state: &AppState,
+    current_config: ConfigRecommendation,
+    feedback: String,
+    data_stats: DataStats,
+    reset: Option<bool>,
+) -> Result<ConfigRefinement, String> {
+    let current_json = serde_json::to_value(&current_config).map_err(|e| e.to_string())?;
+
+    let mut conversation = state.config_conversation.lock().await;
+    if reset.unwrap_or(false) {
+        conversation.clear();
+    }
+
+    let prompt = format!(
+        "Current config:\n{}\n\nData samples: {}\nAvg tokens: {}\nMax tokens: {}\n\n\
+         Feedback: {}\n\n\
+         Respond with the full adjusted config as JSON, using the same fields.",
+        serde_json::to_string_pretty(&current_json).map_err(|e| e.to_string())?,
+        data_stats.num_samples,
+        data_stats.avg_tokens_per_sample,
+        data_stats.max_tokens,
+        feedback
+    );
+
+    let mut messages = conversation.clone();
+    messages.push(Message {
+        role: "user".to_string(),
+        content: prompt,
+    });
+
+    let response = {
+        let client = state.anthropic.lock().await;
+        client
+            .chat_with_agent_history(AgentType::Config, messages.clone())
+            .await
+            .map_err(|e| e.to_string())?
+    };
+    state.record_usage(AgentType::Config, response.usage.as_ref()).await;
+    response.ensure_complete().map_err(|e| e.to_string())?;
+
+    let json_str = anthropic::extract_json(&response.content).map_err(|e| e.to_string())?;
+    let mut refined_json = current_json.clone();
+    let patch: Value = serde_json::from_str(&json_str)
+        .map_err(|e| format!("Invalid config from agent: {}", e))?;
+    merge_json(&mut refined_json, patch);
+    let config: ConfigRecommendation = serde_json::from_value(refined_json.clone())
+        .map_err(|e| format!("Invalid config from agent: {}", e))?;
+
+    messages.push(Message {
+        role: "assistant".to_string(),
+        content: response.content,
+    });
+    let excess = messages.len().saturating_sub(MAX_REFINEMENT_HISTORY);
+    messages.drain(..excess);
+    *conversation = messages;
+
+    let mut changes = Vec::new();
+    diff_json("", &current_json, &refined_json, &mut changes);
+
+    Ok(ConfigRefinement { config, changes })
+}
+
+/// Collect leaf-level differences between two JSON values
+pub fn diff_json(path: &str, before: &Value, after: &Value, changes: &mut Vec<ConfigChange>) {
+    match (before, after) {
+        (Value::Object(before_map), Value::Object(after_map)) => {
+            let mut keys: Vec<&String> = before_map.keys().chain(after_map.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let child = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                diff_json(
+                    &child,
+                    before_map.get(key).unwrap_or(&Value::Null),
+                    after_map.get(key).unwrap_or(&Value::Null),
+                    changes,
+                );
+            }
+        }
+        _ if before != after => changes.push(ConfigChange {
+            field: path.to_string(),
+            before: before.clone(),
+            after: after.clone(),
+        }),
+        _ => {}
+    }
+}
+
+// ============ Research Brief ============
+
+/// Example count used when neither the research nor the intent suggests one
+const DEFAULT_BRIEF_EXAMPLE_COUNT: u32 = 100;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenerationBrief {
+    pub recommended_example_count: u32,
+    /// Scenarios the generated examples should cover
+    #[serde(default)]
+    pub scenarios: Vec<String>,
+    #[serde(default)]
+    pub style_guidance: Vec<String>,
+    /// Condensed guidance to pass to `generate_synthetic_data` as `research_context`
+    #[serde(default)]
+    pub style_hints: String,
+}
+
+/// Condense research findings into a data-generation brief
+#[tauri::command]
+pub async fn research_to_brief(
+    state: State<'_, AppState>,
+    research_result: ResearchResponse,
+    intent: TrainingIntent,
+) -> Result<GenerationBrief, String> {
+    let result = research_to_brief_inner(&state, research_result, intent).await;
+    state.capture("research_to_brief", result).await
+}
+
+async fn research_to_brief_inner(
+    state: &AppState,
+    research_result: ResearchResponse,
+    intent: TrainingIntent,
+) -> Result<GenerationBrief, String> {
+    let bullets = |items: &[String]| {
+        items.iter().map(|i| format!("- {}", i)).collect::<Vec<_>>().join("\n")
+    };
+    let prompt = format!(
+        "Turn this research into a brief for generating synthetic training data.\n\n\
+         Task: {}\nDomain: {}\nStyle: {}\nSuggested example count: {}\n\n\
+         Research summary: {}\n\nBest practices:\n{}\n\nData patterns:\n{}\n\n\
+         Pitfalls:\n{}\n\n\
+         Respond with JSON only: {{\"recommended_example_count\": number, \
+         \"scenarios\": [string], \"style_guidance\": [string], \
+         \"style_hints\": string}}. `style_hints` is one concise paragraph \
+         a data generator can follow directly.",
+        intent.task_description,
+        intent.domain,
+        intent.style.as_deref().unwrap_or("unspecified"),
+        intent
+            .suggested_example_count
+            .map(|n| n.to_string())
+            .unwrap_or_else(|| "unspecified".to_string()),
+        research_result.summary,
+        bullets(&research_result.best_practices),
+        bullets(&research_result.data_patterns),
+        bullets(&research_result.pitfalls),
+    );
+
+    let response = {
+        let client = state.anthropic.lock().await;
+        client
+            .chat_with_agent(AgentType::General, &prompt)
+            .await
+            .map_err(|e| e.to_string())?
+    };
+    state.record_usage(AgentType::General, response.usage.as_ref()).await;
+    response.ensure_complete().map_err(|e| e.to_string())?;
+
+    let json_str = anthropic::extract_json(&response.content).map_err(|e| e.to_string())?;
+    let mut brief: Value = serde_json::from_str(&json_str)
+        .map_err(|e| format!("Invalid brief from agent: {}", e))?;
+    if brief.get("recommended_example_count").and_then(Value::as_u64).is_none() {
+        let fallback = intent
+            .suggested_example_count
+            .unwrap_or(DEFAULT_BRIEF_EXAMPLE_COUNT);
+        brief["recommended_example_count"] = fallback.into();
+    }
+    let mut brief: GenerationBrief = serde_json::from_value(brief)
+        .map_err(|e| format!("Invalid brief from agent: {}", e))?;
+
+    if brief.style_hints.trim().is_empty() {
+        brief.style_hints = brief.style_guidance.join(" ");
+    }
+
+    Ok(brief)
+}
+
 // ============ General Chat ============
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatResponse {
     pub message: String,
     pub should_speak: bool,
+    pub stop_reason: Option<String>,
+    /// The model declined to answer; `message` may be empty or a refusal
+    pub refused: bool,
+    /// The reply hit the token limit and is incomplete
+    pub truncated: bool,
 }
 
 /// General chat with Claude agent
+///
+/// `temperature` and `max_tokens` override the agent's stored settings for this call.
 #[tauri::command]
 pub async fn chat_with_agent(
     state: State<'_, AppState>,
     message: String,
     agent_type: Option<String>,
+    temperature: Option<f32>,
+    max_tokens: Option<u32>,
+) -> Result<ChatResponse, String> {
+    let result = chat_with_agent_inner(&state, message, agent_type, temperature, max_tokens).await;
+    state.capture("chat_with_agent", result).await
+}
+
+async fn chat_with_agent_inner(
+    state: &AppState,
+    message: String,
+    agent_type: Option<String>,
+    temperature: Option<f32>,
+    max_tokens: Option<u32>,
 ) -> Result<ChatResponse, String> {
     let client = state.anthropic.lock().await;
 
-    let agent = match agent_type.as_deref() {
-        Some("intent") => AgentType::Intent,
-        Some("validation") => AgentType::Validation,
-        Some("config") => AgentType::Config,
-        _ => AgentType::General,
+    let agent = agent_type
+        .as_deref()
+        .and_then(AgentType::from_name)
+        .unwrap_or(AgentType::General);
+
+    let messages = vec![Message {
+        role: "user".to_string(),
+        content: message,
+    }];
+    let overrides = AgentSettings {
+        temperature,
+        max_tokens,
+        model: None,
     };
 
     let response = client
-        .chat_with_agent(agent, &message)
+        .chat_with_agent_settings(agent, messages, overrides)
         .await
         .map_err(|e| e.to_string())?;
+    state.record_usage(agent, response.usage.as_ref()).await;
 
     Ok(ChatResponse {
+        refused: response.was_refused(),
+        truncated: response.was_truncated(),
+        should_speak: !response.was_refused(),
         message: response.content,
-        should_speak: true,
+        stop_reason: response.stop_reason,
+    })
+}
+
+// ============ Streaming Chat ============
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatDeltaEvent {
+    pub request_id: String,
+    pub delta: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatCancelledEvent {
+    pub request_id: String,
+    pub partial: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatStreamResponse {
+    pub request_id: String,
+    pub message: String,
+    pub stop_reason: Option<String>,
+    /// Stopped by `cancel_chat`; `message` holds the partial reply
+    pub cancelled: bool,
+    pub refused: bool,
+    pub truncated: bool,
+}
+
+/// Stream a reply from a Claude agent as `chat-delta` events
+///
+/// The stream can be stopped with `cancel_chat(request_id)`, in which case a
+/// `chat-cancelled` event carries the partial text.
+#[tauri::command]
+pub async fn chat_stream(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    message: String,
+    agent_type: Option<String>,
+    request_id: String,
+) -> Result<ChatStreamResponse, String> {
+    let result = chat_stream_inner(app, &state, message, agent_type, request_id).await;
+    state.capture("chat_stream", result).await
+}
+
+async fn chat_stream_inner(
+    app: AppHandle,
+    state: &AppState,
+    message: String,
+    agent_type: Option<String>,
+    request_id: String,
+) -> Result<ChatStreamResponse, String> {
+    let agent = agent_type
+        .as_deref()
+        .and_then(AgentType::from_name)
+        .unwrap_or(AgentType::General);
+
+    let cancel = state.register_cancellation(&request_id).await;
+    let streamed = {
+        let client = state.anthropic.lock().await;
+        client
+            .chat_stream_with_agent(agent, &message, &cancel, |delta| {
+                let event = ChatDeltaEvent {
+                    request_id: request_id.clone(),
+                    delta: delta.to_string(),
+                };
+                if let Err(e) = app.emit("chat-delta", event) {
+                    tracing::warn!("Failed to emit chat delta: {}", e);
+                }
+            })
+            .await
+    };
+    state.finish_cancellation(&request_id).await;
+    let streamed = streamed.map_err(|e| e.to_string())?;
+    state.record_usage(agent, streamed.usage.as_ref()).await;
+
+    if streamed.cancelled {
+        let event = ChatCancelledEvent {
+            request_id: request_id.clone(),
+            partial: streamed.content.clone(),
+        };
+        if let Err(e) = app.emit("chat-cancelled", event) {
+            tracing::warn!("Failed to emit chat cancellation: {}", e);
+        }
+    }
+
+    Ok(ChatStreamResponse {
+        refused: streamed.stop_reason.as_deref() == Some("refusal"),
+        truncated: streamed.stop_reason.as_deref() == Some("max_tokens"),
+        request_id,
+        message: streamed.content,
+        stop_reason: streamed.stop_reason,
+        cancelled: streamed.cancelled,
+    })
+}
+
+/// Stop an in-progress `chat_stream` (e.g. when the user barges in)
+#[tauri::command]
+pub async fn cancel_chat(state: State<'_, AppState>, request_id: String) -> Result<bool, String> {
+    Ok(state.cancel(&request_id).await)
+}
+
+// ============ Agent Usage ============
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentUsageEntry {
+    pub agent: AgentType,
+    #[serde(flatten)]
+    pub usage: AgentUsage,
+    /// Fraction of the session's estimated Claude cost (0-1)
+    pub cost_share: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentUsageReport {
+    /// Agents that made calls this session, most expensive first
+    pub agents: Vec<AgentUsageEntry>,
+    pub total: AgentUsage,
+}
+
+/// Claude calls, tokens and estimated cost per agent since launch (or the last reset)
+#[tauri::command]
+pub async fn get_agent_usage(
+    state: State<'_, AppState>,
+    reset: Option<bool>,
+) -> Result<AgentUsageReport, String> {
+    let mut usage = state.agent_usage.lock().await;
+    let snapshot: Vec<(AgentType, AgentUsage)> = if reset.unwrap_or(false) {
+        usage.drain().collect()
+    } else {
+        usage.iter().map(|(agent, totals)| (*agent, totals.clone())).collect()
+    };
+
+    let mut total = AgentUsage::default();
+    for (_, totals) in &snapshot {
+        total.calls += totals.calls;
+        total.input_tokens += totals.input_tokens;
+        total.output_tokens += totals.output_tokens;
+        total.estimated_cost_usd += totals.estimated_cost_usd;
+    }
+
+    let mut agents: Vec<AgentUsageEntry> = snapshot
+        .into_iter()
+        .map(|(agent, usage)| AgentUsageEntry {
+            agent,
+            cost_share: if total.estimated_cost_usd > 0.0 {
+                usage.estimated_cost_usd / total.estimated_cost_usd
+            } else {
+                0.0
+            },
+            usage,
+        })
+        .collect();
+    agents.sort_by(|a, b| {
+        b.usage
+            .estimated_cost_usd
+            .total_cmp(&a.usage.estimated_cost_usd)
+            .then(b.usage.calls.cmp(&a.usage.calls))
+    });
+
+    Ok(AgentUsageReport { agents, total })
+}
+
+// ============ Agent Settings ============
+
+/// Store key holding per-agent generation settings
+const AGENT_SETTINGS_KEY: &str = "agent_settings";
+
+/// Generation settings in effect for an agent
+#[tauri::command]
+pub async fn get_agent_settings(
+    state: State<'_, AppState>,
+    agent_type: String,
+) -> Result<AgentSettings, String> {
+    let result = get_agent_settings_inner(&state, agent_type).await;
+    state.capture("get_agent_settings", result).await
+}
+
+async fn get_agent_settings_inner(
+    state: &AppState,
+    agent_type: String,
+) -> Result<AgentSettings, String> {
+    let agent = parse_agent_type(&agent_type)?;
+    let client = state.anthropic.lock().await;
+
+    Ok(client.agent_settings(agent))
+}
+
+/// Set an agent's default generation settings (persisted across restarts);
+/// unset fields use the built-in defaults
+#[tauri::command]
+pub async fn set_agent_settings(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    agent_type: String,
+    settings: AgentSettings,
+) -> Result<AgentSettings, String> {
+    let result = set_agent_settings_inner(app, &state, agent_type, settings).await;
+    state.capture("set_agent_settings", result).await
+}
+
+async fn set_agent_settings_inner(
+    app: AppHandle,
+    state: &AppState,
+    agent_type: String,
+    settings: AgentSettings,
+) -> Result<AgentSettings, String> {
+    let agent = parse_agent_type(&agent_type)?;
+    if let Some(temperature) = settings.temperature {
+        if !(0.0..=1.0).contains(&temperature) {
+            return Err(format!("Temperature must be between 0 and 1: {}", temperature));
+        }
+    }
+    if settings.max_tokens == Some(0) {
+        return Err("max_tokens must be greater than 0".to_string());
+    }
+    if settings.model.as_deref().is_some_and(|m| m.trim().is_empty()) {
+        return Err("Model cannot be empty".to_string());
+    }
+
+    let mut client = state.anthropic.lock().await;
+    client.set_agent_settings(agent, settings);
+
+    let store = app.store(STORE_FILE).map_err(|e| e.to_string())?;
+    let value = serde_json::to_value(client.stored_agent_settings()).map_err(|e| e.to_string())?;
+    store.set(AGENT_SETTINGS_KEY, value);
+    store.save().map_err(|e| e.to_string())?;
+
+    Ok(client.agent_settings(agent))
+}
+
+/// Apply persisted agent settings to a freshly created state
+pub fn restore_agent_settings(app: &AppHandle, state: &mut AppState) {
+    let Some(value) = app.store(STORE_FILE).ok().and_then(|s| s.get(AGENT_SETTINGS_KEY)) else {
+        return;
+    };
+
+    match serde_json::from_value::<HashMap<AgentType, AgentSettings>>(value) {
+        Ok(settings) => {
+            let client = state.anthropic.get_mut();
+            for (agent, agent_settings) in settings {
+                client.set_agent_settings(agent, agent_settings);
+            }
+        }
+        Err(e) => tracing::warn!("Ignoring invalid stored agent settings: {}", e),
+    }
+}
+
+// ============ Agent Prompts ============
+
+/// Store key holding per-agent system prompt overrides
+const AGENT_PROMPTS_KEY: &str = "agent_prompts";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentPrompt {
+    pub agent_type: AgentType,
+    pub prompt: String,
+    /// True when the prompt differs from the embedded default
+    pub is_override: bool,
+}
+
+fn parse_agent_type(agent_type: &str) -> Result<AgentType, String> {
+    AgentType::from_name(agent_type).ok_or_else(|| format!("Unknown agent type: {}", agent_type))
+}
+
+/// Get the system prompt currently used by an agent
+#[tauri::command]
+pub async fn get_agent_prompt(
+    state: State<'_, AppState>,
+    agent_type: String,
+) -> Result<AgentPrompt, String> {
+    let result = get_agent_prompt_inner(&state, agent_type).await;
+    state.capture("get_agent_prompt", result).await
+}
+
+async fn get_agent_prompt_inner(
+    state: &AppState,
+    agent_type: String,
+) -> Result<AgentPrompt, String> {
+    let agent = parse_agent_type(&agent_type)?;
+    let client = state.anthropic.lock().await;
+
+    Ok(AgentPrompt {
+        agent_type: agent,
+        prompt: client.agent_prompt(agent).to_string(),
+        is_override: client.has_prompt_override(agent),
+    })
+}
+
+/// Override an agent's system prompt (persisted across restarts)
+#[tauri::command]
+pub async fn set_agent_prompt(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    agent_type: String,
+    prompt: String,
+) -> Result<AgentPrompt, String> {
+    let result = set_agent_prompt_inner(app, &state, agent_type, prompt).await;
+    state.capture("set_agent_prompt", result).await
+}
+
+async fn set_agent_prompt_inner(
+    app: AppHandle,
+    state: &AppState,
+    agent_type: String,
+    prompt: String,
+) -> Result<AgentPrompt, String> {
+    let agent = parse_agent_type(&agent_type)?;
+    if prompt.trim().is_empty() {
+        return Err("Prompt cannot be empty".to_string());
+    }
+
+    let mut client = state.anthropic.lock().await;
+    client.set_agent_prompt(agent, prompt.clone());
+    save_agent_prompts(&app, client.prompt_overrides())?;
+
+    Ok(AgentPrompt {
+        agent_type: agent,
+        prompt,
+        is_override: true,
     })
 }
+
+/// Restore an agent's embedded system prompt
+#[tauri::command]
+pub async fn reset_agent_prompt(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    agent_type: String,
+) -> Result<AgentPrompt, String> {
+    let result = reset_agent_prompt_inner(app, &state, agent_type).await;
+    state.capture("reset_agent_prompt", result).await
+}
+
+async fn reset_agent_prompt_inner(
+    app: AppHandle,
+    state: &AppState,
+    agent_type: String,
+) -> Result<AgentPrompt, String> {
+    let agent = parse_agent_type(&agent_type)?;
+
+    let mut client = state.anthropic.lock().await;
+    client.reset_agent_prompt(agent);
+    save_agent_prompts(&app, client.prompt_overrides())?;
+
+    Ok(AgentPrompt {
+        agent_type: agent,
+        prompt: agent.system_prompt().to_string(),
+        is_override: false,
+    })
+}
+
+fn save_agent_prompts(
+    app: &AppHandle,
+    overrides: &HashMap<AgentType, String>,
+) -> Result<(), String> {
+    let store = app.store(STORE_FILE).map_err(|e| e.to_string())?;
+    let value = serde_json::to_value(overrides).map_err(|e| e.to_string())?;
+    store.set(AGENT_PROMPTS_KEY, value);
+    store.save().map_err(|e| e.to_string())
+}
+
+/// Apply persisted prompt overrides to a freshly created state
+pub fn restore_agent_prompts(app: &AppHandle, state: &mut AppState) {
+    let Some(value) = app.store(STORE_FILE).ok().and_then(|s| s.get(AGENT_PROMPTS_KEY)) else {
+        return;
+    };
+
+    match serde_json::from_value::<HashMap<AgentType, String>>(value) {
+        Ok(overrides) => {
+            let client = state.anthropic.get_mut();
+            for (agent, prompt) in overrides {
+                client.set_agent_prompt(agent, prompt);
+            }
+        }
+        Err(e) => tracing::warn!("Ignoring invalid stored agent prompts: {}", e),
+    }
+}
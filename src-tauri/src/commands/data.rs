@@ -2,11 +2,18 @@
 //!
 //! SESSION 2: Implement these commands
 
-use tauri::State;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::time::Instant;
+
+use futures::stream::{self, StreamExt};
+use tauri::{AppHandle, Emitter, State};
 use crate::state::AppState;
-use crate::api::tonic::OutputFormat;
-use crate::commands::agents::TrainingIntent;
+use crate::api::tonic::{self, DataSchema, GenerationRequest, OutputFormat};
+use crate::commands::agents::{IssueSeverity, TrainingIntent, ValidationIssue};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 // ============ Synthetic Data Generation ============
 
@@ -15,6 +22,12 @@ pub struct GenerateSyntheticDataRequest {
     pub intent: TrainingIntent,
     pub num_examples: u32,
     pub research_context: Option<String>,
+    /// Passed to Tonic so the same request can reproduce a dataset
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Records per Tonic request (see `recommended_batch_size`); one request when not set
+    #[serde(default)]
+    pub batch_size: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,6 +42,51 @@ pub struct TrainingExample {
     pub input: String,
     pub output: String,
     pub system: Option<String>,
+    /// Free-form tags, e.g. the source domain for multi-domain generation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<HashMap<String, String>>,
+    /// Relative importance during training; 1.0 when not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight: Option<f32>,
+}
+
+impl TrainingExample {
+    pub fn weight(&self) -> f32 {
+        self.weight.unwrap_or(1.0)
+    }
+}
+
+/// Reject negative or non-finite example weights
+pub fn check_weights(examples: &[TrainingExample]) -> Result<(), String> {
+    match examples
+        .iter()
+        .position(|e| e.weight.is_some_and(|w| !w.is_finite() || w < 0.0))
+    {
+        Some(index) => Err(format!(
+            "Example {} has an invalid weight ({}); weights must be non-negative numbers",
+            index,
+            examples[index].weight()
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Reject raw records whose `weight` is present but not a non-negative number
+pub fn check_record_weights(records: &[Value]) -> Result<(), String> {
+    let invalid = |weight: &Value| match weight {
+        Value::Null => false,
+        other => !other.as_f64().is_some_and(|w| w.is_finite() && w >= 0.0),
+    };
+    match records
+        .iter()
+        .position(|r| r.get("weight").is_some_and(invalid))
+    {
+        Some(index) => Err(format!(
+            "Record {} has an invalid weight ({}); weights must be non-negative numbers",
+            index, records[index]["weight"]
+        )),
+        None => Ok(()),
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -36,6 +94,21 @@ pub struct GenerationMetadata {
     pub source: String, // "tonic" or "uploaded"
     pub prompt_used: Option<String>,
     pub duration_ms: u64,
+    /// Inputs needed to re-run the generation
+    #[serde(default)]
+    pub parameters: Option<GenerationParameters>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenerationParameters {
+    pub task_description: String,
+    pub domains: Vec<String>,
+    /// Examples requested per domain
+    pub num_examples: u32,
+    pub style_hints: Option<String>,
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub batch_size: Option<u32>,
 }
 
 /// Generate synthetic training data
@@ -44,17 +117,43 @@ pub async fn generate_synthetic_data(
     state: State<'_, AppState>,
     request: GenerateSyntheticDataRequest,
 ) -> Result<GeneratedDataset, String> {
+    let result = generate_synthetic_data_inner(&state, request).await;
+    state.capture("generate_synthetic_data", result).await
+}
+
+async fn generate_synthetic_data_inner(
+    state: &AppState,
+    request: GenerateSyntheticDataRequest,
+) -> Result<GeneratedDataset, String> {
+    let started = Instant::now();
     let client = state.tonic.lock().await;
 
-    let examples = client
-        .generate_training_data(
-            &request.intent.task_description,
-            &request.intent.domain,
-            request.num_examples,
-            request.research_context.as_deref(),
-        )
-        .await
-        .map_err(|e| e.to_string())?;
+    let batch_size = request
+        .batch_size
+        .filter(|size| *size > 0)
+        .unwrap_or(request.num_examples)
+        .max(1);
+    let mut examples = Vec::new();
+    let mut remaining = request.num_examples;
+    let mut batch = 0u64;
+    while remaining > 0 {
+        let count = remaining.min(batch_size);
+        // Each batch gets its own seed so batches don't repeat each other
+        let seed = request.seed.map(|seed| seed.wrapping_add(batch));
+        let generated = client
+            .generate_training_data(
+                &request.intent.task_description,
+                &request.intent.domain,
+                count,
+                request.research_context.as_deref(),
+                seed,
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+        examples.extend(generated);
+        remaining -= count;
+        batch += 1;
+    }
 
     let training_examples: Vec<TrainingExample> = examples
         .into_iter()
@@ -62,20 +161,568 @@ pub async fn generate_synthetic_data(
             input: e.input,
             output: e.output,
             system: e.system,
+            metadata: None,
+            weight: None,
         })
         .collect();
 
+    let id = uuid::Uuid::new_v4().to_string();
+    state.register_dataset(&id, training_examples.clone()).await;
+
     Ok(GeneratedDataset {
-        id: uuid::Uuid::new_v4().to_string(),
+        id,
         examples: training_examples,
         generation_metadata: GenerationMetadata {
             source: "tonic".to_string(),
-            prompt_used: Some(request.intent.task_description),
-            duration_ms: 1000,
+            prompt_used: Some(request.intent.task_description.clone()),
+            duration_ms: started.elapsed().as_millis() as u64,
+            parameters: Some(GenerationParameters {
+                task_description: request.intent.task_description,
+                domains: vec![request.intent.domain],
+                num_examples: request.num_examples,
+                style_hints: request.research_context,
+                seed: request.seed,
+                batch_size: request.batch_size,
+            }),
+        },
+    })
+}
+
+// ============ Multi-Domain Generation ============
+
+/// Maximum number of domains generated against Tonic at once
+const MAX_CONCURRENT_GENERATIONS: usize = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultiDomainDataset {
+    pub dataset: GeneratedDataset,
+    pub domain_breakdown: Vec<DomainBreakdown>,
+    /// Cross-domain duplicates dropped when dedup is enabled
+    pub duplicates_removed: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DomainBreakdown {
+    pub domain: String,
+    pub requested: u32,
+    pub generated: u32,
+    pub error: Option<String>,
+}
+
+/// Generate examples for several domains concurrently and combine them
+#[tauri::command]
+pub async fn generate_multi_domain(
+    state: State<'_, AppState>,
+    base_task: String,
+    domains: Vec<String>,
+    per_domain_count: u32,
+    style: Option<String>,
+    dedup: Option<bool>,
+    seed: Option<u64>,
+) -> Result<MultiDomainDataset, String> {
+    let result = generate_multi_domain_inner(
+        &state,
+        base_task,
+        domains,
+        per_domain_count,
+        style,
+        dedup,
+        seed,
+    )
+    .await;
+    state.capture("generate_multi_domain", result).await
+}
+
+async fn generate_multi_domain_inner(
+    state: &AppState,
+    base_task: String,
+    domains: Vec<String>,
+    per_domain_count: u32,
+    style: Option<String>,
+    dedup: Option<bool>,
+    seed: Option<u64>,
+) -> Result<MultiDomainDataset, String> {
+    if domains.is_empty() {
+        return Err("At least one domain is required".to_string());
+    }
+
+    let started = Instant::now();
+    let client = state.tonic.lock().await;
+
+    let parameters = GenerationParameters {
+        task_description: base_task.clone(),
+        domains: domains.clone(),
+        num_examples: per_domain_count,
+        style_hints: style.clone(),
+        seed,
+        batch_size: None,
+    };
+
+    let results: Vec<(String, Result<Vec<TrainingExample>, String>)> = stream::iter(domains)
+        .map(|domain| {
+            let client = &client;
+            let base_task = &base_task;
+            let style = style.as_deref();
+            async move {
+                let result = client
+                    .generate_training_data(base_task, &domain, per_domain_count, style, seed)
+                    .await
+                    .map(|examples| {
+                        examples
+                            .into_iter()
+                            .map(|e| TrainingExample {
+                                input: e.input,
+                                output: e.output,
+                                system: e.system,
+                                metadata: Some(HashMap::from([(
+                                    "domain".to_string(),
+                                    domain.clone(),
+                                )])),
+                                weight: None,
+                            })
+                            .collect()
+                    })
+                    .map_err(|e| e.to_string());
+                (domain, result)
+            }
+        })
+        .buffered(MAX_CONCURRENT_GENERATIONS)
+        .collect()
+        .await;
+
+    let mut examples = Vec::new();
+    let mut domain_breakdown = Vec::new();
+    for (domain, result) in results {
+        let (generated, error) = match result {
+            Ok(domain_examples) => {
+                let count = domain_examples.len() as u32;
+                examples.extend(domain_examples);
+                (count, None)
+            }
+            Err(e) => (0, Some(e)),
+        };
+        domain_breakdown.push(DomainBreakdown {
+            domain,
+            requested: per_domain_count,
+            generated,
+            error,
+        });
+    }
+
+    if domain_breakdown.iter().all(|d| d.error.is_some()) {
+        let errors: Vec<String> = domain_breakdown
+            .iter()
+            .filter_map(|d| d.error.as_ref().map(|e| format!("{}: {}", d.domain, e)))
+            .collect();
+        return Err(format!("Generation failed for all domains: {}", errors.join("; ")));
+    }
+
+    let mut duplicates_removed = 0;
+    if dedup.unwrap_or(false) {
+        let before = examples.len();
+        let mut seen = HashSet::new();
+        examples.retain(|e| seen.insert((normalize_text(&e.input), normalize_text(&e.output))));
+        duplicates_removed = (before - examples.len()) as u32;
+    }
+
+    let id = uuid::Uuid::new_v4().to_string();
+    state.register_dataset(&id, examples.clone()).await;
+
+    Ok(MultiDomainDataset {
+        dataset: GeneratedDataset {
+            id,
+            examples,
+            generation_metadata: GenerationMetadata {
+                source: "tonic".to_string(),
+                prompt_used: Some(base_task),
+                duration_ms: started.elapsed().as_millis() as u64,
+                parameters: Some(parameters),
+            },
         },
+        domain_breakdown,
+        duplicates_removed,
+    })
+}
+
+/// Lowercase and collapse whitespace for duplicate comparison
+pub fn normalize_text(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+// ============ Schema Preview ============
+
+/// Default and maximum number of records generated for a schema preview
+const DEFAULT_SCHEMA_PREVIEW_COUNT: u32 = 3;
+const MAX_SCHEMA_PREVIEW_COUNT: u32 = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaPreview {
+    pub records: Vec<Value>,
+    /// Schema fields absent from at least one sample record
+    pub missing_fields: Vec<String>,
+    /// Fields present in the samples but not declared in the schema
+    pub extra_fields: Vec<String>,
+    pub generation_id: String,
+}
+
+/// Generate a handful of records with a custom schema to check its effect
+#[tauri::command]
+pub async fn preview_schema_generation(
+    state: State<'_, AppState>,
+    schema: DataSchema,
+    prompt: String,
+    sample_count: Option<u32>,
+) -> Result<SchemaPreview, String> {
+    let result = preview_schema_generation_inner(&state, schema, prompt, sample_count).await;
+    state.capture("preview_schema_generation", result).await
+}
+
+async fn preview_schema_generation_inner(
+    state: &AppState,
+    schema: DataSchema,
+    prompt: String,
+    sample_count: Option<u32>,
+) -> Result<SchemaPreview, String> {
+    schema.validate().map_err(|e| e.to_string())?;
+
+    let num_records = sample_count
+        .unwrap_or(DEFAULT_SCHEMA_PREVIEW_COUNT)
+        .clamp(1, MAX_SCHEMA_PREVIEW_COUNT);
+
+    let client = state.tonic.lock().await;
+    let generation = client
+        .generate(GenerationRequest {
+            prompt,
+            num_records,
+            schema: Some(schema.clone()),
+            format: OutputFormat::Jsonl,
+            seed: None,
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let records = generation.records.unwrap_or_default();
+    // Names are compared trimmed, the same way `DataSchema::validate` checks them
+    let declared: HashSet<&str> = schema.fields.iter().map(|f| f.name.trim()).collect();
+    let has_field = |record: &Value, name: &str| {
+        record
+            .as_object()
+            .is_some_and(|obj| obj.keys().any(|key| key.trim() == name))
+    };
+
+    let missing_fields = schema
+        .fields
+        .iter()
+        .map(|f| f.name.trim())
+        .filter(|name| records.iter().any(|r| !has_field(r, name)))
+        .map(str::to_string)
+        .collect();
+
+    let mut extra_fields: Vec<String> = records
+        .iter()
+        .filter_map(|r| r.as_object())
+        .flat_map(|obj| obj.keys())
+        .filter(|key| !declared.contains(key.trim()))
+        .cloned()
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    extra_fields.sort();
+
+    Ok(SchemaPreview {
+        records,
+        missing_fields,
+        extra_fields,
+        generation_id: generation.metadata.generation_id,
+    })
+}
+
+// ============ Batch Sizing ============
+
+/// Request sizes tried by `recommended_batch_size`, smallest first
+const DEFAULT_PROBE_SIZES: &[u32] = &[10, 25, 50];
+/// Largest probe allowed, to bound the cost of sizing
+const MAX_PROBE_SIZE: u32 = 100;
+/// Smallest batch size ever recommended
+const MIN_BATCH_SIZE: u32 = 5;
+/// A probe is healthy when at least this share of requested records parse...
+const MIN_PARSE_RATE: f64 = 0.95;
+/// ...and at least this share of parsed records have distinct inputs
+const MIN_DIVERSITY: f64 = 0.9;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchProbe {
+    pub requested: u32,
+    /// Records that parsed into examples with non-empty input and output
+    pub parsed: u32,
+    /// parsed / requested
+    pub parse_rate: f64,
+    /// Lines of the response that weren't valid JSON, typically a truncated tail
+    pub unparsed_lines: u32,
+    /// Distinct (normalized) inputs / parsed
+    pub diversity: f64,
+    pub healthy: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchSizeRecommendation {
+    /// Suggested `num_records` per generation request
+    pub batch_size: u32,
+    pub probes: Vec<BatchProbe>,
+    pub reasoning: String,
+}
+
+/// Probe Tonic with increasingly large requests for a prompt and suggest the
+/// largest per-request `num_records` that still parsed cleanly without repetition
+#[tauri::command]
+pub async fn recommended_batch_size(
+    state: State<'_, AppState>,
+    prompt: String,
+    probe_sizes: Option<Vec<u32>>,
+) -> Result<BatchSizeRecommendation, String> {
+    let result = recommended_batch_size_inner(&state, prompt, probe_sizes).await;
+    state.capture("recommended_batch_size", result).await
+}
+
+async fn recommended_batch_size_inner(
+    state: &AppState,
+    prompt: String,
+    probe_sizes: Option<Vec<u32>>,
+) -> Result<BatchSizeRecommendation, String> {
+    if prompt.trim().is_empty() {
+        return Err("Prompt is empty".to_string());
+    }
+    let mut sizes = probe_sizes.unwrap_or_else(|| DEFAULT_PROBE_SIZES.to_vec());
+    sizes.retain(|size| *size > 0);
+    sizes.iter_mut().for_each(|size| *size = (*size).min(MAX_PROBE_SIZE));
+    sizes.sort_unstable();
+    sizes.dedup();
+    if sizes.is_empty() {
+        return Err("No probe sizes given".to_string());
+    }
+
+    let client = state.tonic.lock().await;
+    let mut probes: Vec<BatchProbe> = Vec::new();
+    for size in sizes {
+        let generation = client
+            .generate(GenerationRequest {
+                prompt: prompt.clone(),
+                num_records: size,
+                schema: Some(tonic::training_example_schema()),
+                format: OutputFormat::Jsonl,
+                seed: None,
+            })
+            .await;
+
+        let probe = match generation {
+            Ok(generation) => probe_quality(
+                size,
+                generation.records.unwrap_or_default(),
+                generation.invalid_lines.len() as u32,
+            ),
+            // Nothing to size against if even the smallest request fails
+            Err(e) if probes.is_empty() => return Err(e.to_string()),
+            Err(e) => BatchProbe {
+                requested: size,
+                parsed: 0,
+                parse_rate: 0.0,
+                unparsed_lines: 0,
+                diversity: 0.0,
+                healthy: false,
+                error: Some(e.to_string()),
+            },
+        };
+        let healthy = probe.healthy;
+        probes.push(probe);
+        // Larger requests only degrade further
+        if !healthy {
+            break;
+        }
+    }
+
+    let largest_healthy = probes.iter().filter(|p| p.healthy).map(|p| p.requested).max();
+    let failed = probes.iter().find(|p| !p.healthy);
+    let (batch_size, reasoning) = match (largest_healthy, failed) {
+        (Some(size), None) => (
+            size,
+            format!(
+                "Every probe up to {} records parsed cleanly with distinct inputs; \
+                 larger requests were not tested",
+                size
+            ),
+        ),
+        (Some(size), Some(failed)) => (
+            size,
+            format!(
+                "{} records per request held up, but {} did not ({})",
+                size,
+                failed.requested,
+                describe_probe_failure(failed)
+            ),
+        ),
+        (None, Some(failed)) => (
+            MIN_BATCH_SIZE.min(failed.requested),
+            format!(
+                "Even {} records per request degraded ({}); use small batches \
+                 and consider making the prompt more specific",
+                failed.requested,
+                describe_probe_failure(failed)
+            ),
+        ),
+        (None, None) => unreachable!("at least one probe runs"),
+    };
+
+    Ok(BatchSizeRecommendation {
+        batch_size,
+        probes,
+        reasoning,
     })
 }
 
+fn probe_quality(requested: u32, records: Vec<Value>, unparsed_lines: u32) -> BatchProbe {
+    let examples: Vec<TrainingExample> = records
+        .into_iter()
+        .filter_map(|record| serde_json::from_value::<TrainingExample>(record).ok())
+        .filter(|e| !e.input.trim().is_empty() && !e.output.trim().is_empty())
+        .collect();
+    let parsed = examples.len() as u32;
+    let distinct = examples
+        .iter()
+        .map(|e| normalize_text(&e.input))
+        .collect::<HashSet<_>>()
+        .len();
+
+    // Over-delivery shouldn't count as better than a complete response
+    let parse_rate = (parsed as f64 / requested as f64).min(1.0);
+    let diversity = if parsed > 0 { distinct as f64 / parsed as f64 } else { 0.0 };
+    BatchProbe {
+        requested,
+        parsed,
+        parse_rate,
+        unparsed_lines,
+        diversity,
+        healthy: parse_rate >= MIN_PARSE_RATE && diversity >= MIN_DIVERSITY,
+        error: None,
+    }
+}
+
+fn describe_probe_failure(probe: &BatchProbe) -> String {
+    if let Some(error) = &probe.error {
+        return format!("request failed: {}", error);
+    }
+    let mut problems = Vec::new();
+    if probe.parse_rate < MIN_PARSE_RATE {
+        let cause = if probe.unparsed_lines > 0 {
+            format!("{} line(s) were cut off or malformed", probe.unparsed_lines)
+        } else {
+            "suggesting truncation".to_string()
+        };
+        problems.push(format!(
+            "only {} of {} records were usable, {}",
+            probe.parsed, probe.requested, cause
+        ));
+    }
+    if probe.diversity < MIN_DIVERSITY {
+        problems.push(format!(
+            "{:.0}% of inputs were repeats",
+            (1.0 - probe.diversity) * 100.0
+        ));
+    }
+    problems.join("; ")
+}
+
+// ============ Generation Progress ============
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenerationProgress {
+    pub generation_id: String,
+    pub status: String,
+    /// Completion from 0 to 1
+    pub fraction: f64,
+    pub records_completed: Option<u32>,
+    pub records_requested: Option<u32>,
+    /// True when `fraction` was estimated from elapsed time rather than reported
+    pub estimated: bool,
+}
+
+/// Report a generation job's progress and emit it as a `generation-progress` event
+///
+/// When Tonic doesn't report granular progress, pass the job's start time and
+/// the preview's estimated duration to get a time-based estimate instead.
+#[tauri::command]
+pub async fn get_generation_status(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    generation_id: String,
+    started_at: Option<String>,
+    estimated_duration_seconds: Option<u32>,
+) -> Result<GenerationProgress, String> {
+    let result = get_generation_status_inner(
+        app,
+        &state,
+        generation_id,
+        started_at,
+        estimated_duration_seconds,
+    )
+    .await;
+    state.capture("get_generation_status", result).await
+}
+
+async fn get_generation_status_inner(
+    app: AppHandle,
+    state: &AppState,
+    generation_id: String,
+    started_at: Option<String>,
+    estimated_duration_seconds: Option<u32>,
+) -> Result<GenerationProgress, String> {
+    let status = {
+        let client = state.tonic.lock().await;
+        client
+            .get_generation_status(&generation_id)
+            .await
+            .map_err(|e| e.to_string())?
+    };
+
+    let (fraction, estimated) = if status.status == "completed" {
+        (1.0, false)
+    } else if let Some(fraction) = status.fraction() {
+        (fraction, false)
+    } else if status.is_finished() {
+        (0.0, false)
+    } else {
+        let elapsed = started_at
+            .as_deref()
+            .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+            .map(|start| (chrono::Utc::now() - start.to_utc()).num_seconds());
+        match (elapsed, estimated_duration_seconds) {
+            // Never claim completion from an estimate alone
+            (Some(elapsed), Some(total)) if total > 0 => {
+                ((elapsed.max(0) as f64 / total as f64).min(0.99), true)
+            }
+            _ => (0.0, true),
+        }
+    };
+
+    let progress = GenerationProgress {
+        generation_id,
+        status: status.status,
+        fraction,
+        records_completed: status.records_completed,
+        records_requested: status.records_requested,
+        estimated,
+    };
+
+    if let Err(e) = app.emit("generation-progress", progress.clone()) {
+        tracing::warn!("Failed to emit generation progress: {}", e);
+    }
+
+    Ok(progress)
+}
+
 // ============ File Upload ============
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -83,6 +730,10 @@ pub struct UploadedDataset {
     pub id: String,
     pub examples: Vec<TrainingExample>,
     pub file_metadata: FileMetadata,
+    /// Which CSV columns became input/output/system, for the user to confirm
+    pub csv_columns: Option<CsvColumnMapping>,
+    /// CSV rows that couldn't be read as examples
+    pub skipped_rows: Vec<SkippedRow>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -96,8 +747,20 @@ pub struct FileMetadata {
 /// Upload and parse a dataset file
 #[tauri::command]
 pub async fn upload_dataset(
+    state: State<'_, AppState>,
+    file_path: String,
+    format: Option<String>,
+    csv_options: Option<CsvOptions>,
+) -> Result<UploadedDataset, String> {
+    let result = upload_dataset_inner(&state, file_path, format, csv_options).await;
+    state.capture("upload_dataset", result).await
+}
+
+async fn upload_dataset_inner(
+    state: &AppState,
     file_path: String,
     format: Option<String>,
+    csv_options: Option<CsvOptions>,
 ) -> Result<UploadedDataset, String> {
     // Read the file
     let content = std::fs::read_to_string(&file_path)
@@ -112,29 +775,25 @@ pub async fn upload_dataset(
         .unwrap_or("unknown")
         .to_string();
 
-    // Detect format
-    let detected_format = format.unwrap_or_else(|| {
-        if filename.ends_with(".jsonl") {
-            "jsonl".to_string()
-        } else if filename.ends_with(".json") {
-            "json".to_string()
-        } else if filename.ends_with(".csv") {
-            "csv".to_string()
-        } else {
-            "unknown".to_string()
-        }
-    });
+    let detected_format = detect_format(&filename, format);
 
     // Parse based on format
-    let examples = match detected_format.as_str() {
-        "jsonl" => parse_jsonl(&content)?,
-        "json" => parse_json(&content)?,
-        "csv" => parse_csv(&content)?,
+    let (examples, csv_columns, skipped_rows) = match detected_format.as_str() {
+        "jsonl" => (parse_jsonl(&content)?, None, Vec::new()),
+        "json" => (parse_json(&content)?, None, Vec::new()),
+        "csv" => {
+            let parsed = parse_csv_mapped(&content, &csv_options.unwrap_or_default())?;
+            (parsed.examples, Some(parsed.mapping), parsed.skipped_rows)
+        }
         _ => return Err(format!("Unsupported format: {}", detected_format)),
     };
+    check_weights(&examples)?;
+
+    let id = uuid::Uuid::new_v4().to_string();
+    state.register_dataset(&id, examples.clone()).await;
 
     Ok(UploadedDataset {
-        id: uuid::Uuid::new_v4().to_string(),
+        id,
         examples: examples.clone(),
         file_metadata: FileMetadata {
             filename,
@@ -142,9 +801,51 @@ pub async fn upload_dataset(
             size_bytes: file_metadata.len(),
             row_count: examples.len() as u32,
         },
+        csv_columns,
+        skipped_rows,
+    })
+}
+
+/// Detect a dataset format from an explicit hint or the file extension
+pub fn detect_format(filename: &str, format: Option<String>) -> String {
+    format.unwrap_or_else(|| {
+        if filename.ends_with(".jsonl") {
+            "jsonl".to_string()
+        } else if filename.ends_with(".json") {
+            "json".to_string()
+        } else if filename.ends_with(".csv") {
+            "csv".to_string()
+        } else {
+            "unknown".to_string()
+        }
     })
 }
 
+/// Read a dataset file as raw JSON records, without forcing the
+/// input/output shape (CSV rows become input/output/system objects)
+pub fn read_records(file_path: &str, format: Option<String>) -> Result<Vec<Value>, String> {
+    let content = std::fs::read_to_string(file_path)
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+
+    match detect_format(file_path, format).as_str() {
+        "jsonl" => content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str::<Value>(line)
+                    .map_err(|e| format!("Failed to parse JSONL line: {}", e))
+            })
+            .collect(),
+        "json" => serde_json::from_str::<Vec<Value>>(&content)
+            .map_err(|e| format!("Failed to parse JSON: {}", e)),
+        "csv" => parse_csv(&content, &CsvOptions::default())?
+            .into_iter()
+            .map(|e| serde_json::to_value(e).map_err(|e| e.to_string()))
+            .collect(),
+        other => Err(format!("Unsupported format: {}", other)),
+    }
+}
+
 fn parse_jsonl(content: &str) -> Result<Vec<TrainingExample>, String> {
     content
         .lines()
@@ -161,113 +862,1248 @@ fn parse_json(content: &str) -> Result<Vec<TrainingExample>, String> {
         .map_err(|e| format!("Failed to parse JSON: {}", e))
 }
 
-fn parse_csv(content: &str) -> Result<Vec<TrainingExample>, String> {
-    let mut examples = Vec::new();
-    let mut lines = content.lines();
+/// How to read a CSV file's columns
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CsvOptions {
+    /// Field delimiter, detected from the first line when not set
+    pub delimiter: Option<char>,
+    /// The file has no header row; columns must be given by position
+    #[serde(default)]
+    pub no_header: bool,
+    /// Input column, by header name or zero-based position
+    pub input_column: Option<String>,
+    /// Columns joined (one per line) into `output`, by header name or position
+    pub output_columns: Option<Vec<String>>,
+    /// System prompt column, by header name or position
+    pub system_column: Option<String>,
+    /// Example weight column, by header name or position
+    #[serde(default)]
+    pub weight_column: Option<String>,
+}
 
-    // Skip header
-    let header = lines.next().ok_or("Empty CSV file")?;
-    let headers: Vec<&str> = header.split(',').map(|s| s.trim()).collect();
+/// Delimiters considered when auto-detecting
+const CSV_DELIMITERS: &[char] = &[',', ';', '\t', '|'];
 
-    // Find column indices
-    let input_idx = headers.iter().position(|h| *h == "input" || *h == "prompt")
-        .ok_or("CSV must have 'input' or 'prompt' column")?;
-    let output_idx = headers.iter().position(|h| *h == "output" || *h == "completion" || *h == "response")
-        .ok_or("CSV must have 'output', 'completion', or 'response' column")?;
-    let system_idx = headers.iter().position(|h| *h == "system");
+/// Normalized header names accepted for each field when no column is given
+const INPUT_HEADERS: &[&str] = &["input", "prompt", "question", "query", "instruction", "user"];
+const OUTPUT_HEADERS: &[&str] =
+    &["output", "completion", "response", "answer", "reply", "assistant", "target"];
+const SYSTEM_HEADERS: &[&str] = &["system", "system prompt", "system message"];
+const WEIGHT_HEADERS: &[&str] = &["weight", "sample weight", "example weight"];
+/// Example metadata is written as a JSON object in this column
+const METADATA_HEADER: &str = "metadata";
 
-    for line in lines {
-        if line.trim().is_empty() {
-            continue;
-        }
+/// The CSV columns each training field was read from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CsvColumnMapping {
+    pub delimiter: char,
+    /// Header as written in the file, or "column N" without a header
+    pub input: String,
+    pub output: Vec<String>,
+    pub system: Option<String>,
+    pub weight: Option<String>,
+    #[serde(default)]
+    pub metadata: Option<String>,
+}
 
-        let cols: Vec<&str> = line.split(',').collect();
-        if cols.len() <= input_idx.max(output_idx) {
-            continue;
-        }
+/// A CSV row that couldn't be read as an example
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedRow {
+    /// Line in the file where the row starts (1-based)
+    pub line: u32,
+    pub reason: String,
+}
 
-        examples.push(TrainingExample {
-            input: cols.get(input_idx).unwrap_or(&"").to_string(),
-            output: cols.get(output_idx).unwrap_or(&"").to_string(),
-            system: system_idx.and_then(|i| cols.get(i).map(|s| s.to_string())),
-        });
-    }
+struct ParsedCsv {
+    examples: Vec<TrainingExample>,
+    mapping: CsvColumnMapping,
+    skipped_rows: Vec<SkippedRow>,
+}
 
-    Ok(examples)
+fn parse_csv(content: &str, options: &CsvOptions) -> Result<Vec<TrainingExample>, String> {
+    parse_csv_mapped(content, options).map(|parsed| parsed.examples)
 }
 
-// ============ Data Preview ============
+fn parse_csv_mapped(content: &str, options: &CsvOptions) -> Result<ParsedCsv, String> {
+    let mut records = CsvRecords::new(content.split_inclusive('\n').map(|l| Ok(l.to_string())));
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DataPreview {
-    pub samples: Vec<TrainingExample>,
-    pub total_count: u32,
+    let first = records
+        .next()
+        .ok_or("Empty CSV file")?
+        .map_err(|e| e.to_string())?;
+    let columns = CsvColumns::from_first_line(&first.1, options)?;
+
+    // Without a header the first record is already data
+    let first_row = options.no_header.then_some(Ok(first));
+    let mut examples = Vec::new();
+    let mut skipped_rows = Vec::new();
+    for record in first_row.into_iter().chain(records) {
+        let (line, record) = record.map_err(|e| e.to_string())?;
+        match columns.parse_row(&record).map_err(|e| format!("Line {}: {}", line, e))? {
+            CsvRow::Example(example) => examples.push(example),
+            CsvRow::Blank => {}
+            CsvRow::Short(reason) => skipped_rows.push(SkippedRow { line, reason }),
+        }
+    }
+    Ok(ParsedCsv {
+        examples,
+        mapping: columns.mapping,
+        skipped_rows,
+    })
 }
 
-/// Preview dataset (first N examples)
+/// Show how a CSV file's columns would map to input/output/system, without
+/// reading past the header
 #[tauri::command]
-pub async fn preview_dataset(
-    examples: Vec<TrainingExample>,
-    limit: Option<u32>,
-) -> Result<DataPreview, String> {
-    let limit = limit.unwrap_or(10) as usize;
-    let total = examples.len() as u32;
+pub async fn detect_csv_columns(
+    state: State<'_, AppState>,
+    file_path: String,
+    csv_options: Option<CsvOptions>,
+) -> Result<CsvColumnMapping, String> {
+    let result = detect_csv_columns_inner(file_path, csv_options).await;
+    state.capture("detect_csv_columns", result).await
+}
 
-    Ok(DataPreview {
-        samples: examples.into_iter().take(limit).collect(),
-        total_count: total,
-    })
+async fn detect_csv_columns_inner(
+    file_path: String,
+    csv_options: Option<CsvOptions>,
+) -> Result<CsvColumnMapping, String> {
+    let first = open_csv_records(&file_path)?
+        .next()
+        .ok_or("Empty CSV file")?
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    let columns = CsvColumns::from_first_line(&first.1, &csv_options.unwrap_or_default())?;
+    Ok(columns.mapping)
 }
 
-// ============ Dataset Stats ============
+/// Canonical form of a header for matching: trimmed, lowercase, with a BOM
+/// dropped and `_`/`-` runs treated as spaces
+fn normalize_header(header: &str) -> String {
+    header
+        .trim_start_matches('\u{feff}')
+        .to_lowercase()
+        .split(|c: char| c.is_whitespace() || c == '_' || c == '-')
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DatasetStats {
-    pub num_samples: u32,
-    pub avg_input_length: u32,
-    pub avg_output_length: u32,
-    pub avg_tokens_per_sample: u32,
-    pub max_tokens: u32,
-    pub min_tokens: u32,
-    pub has_system_prompts: bool,
-    pub unique_system_prompts: u32,
+/// Positions of the training fields within a CSV row
+struct CsvColumns {
+    delimiter: char,
+    input: usize,
+    output: Vec<usize>,
+    system: Option<usize>,
+    weight: Option<usize>,
+    metadata: Option<usize>,
+    mapping: CsvColumnMapping,
 }
 
-/// Get statistics about a dataset
-#[tauri::command]
-pub async fn get_dataset_stats(
-    examples: Vec<TrainingExample>,
-) -> Result<DatasetStats, String> {
-    if examples.is_empty() {
-        return Err("Dataset is empty".to_string());
-    }
+impl CsvColumns {
+    /// Resolve columns from the header (or, in `no_header` mode, from positions alone)
+    fn from_first_line(line: &str, options: &CsvOptions) -> Result<Self, String> {
+        let delimiter = options.delimiter.unwrap_or_else(|| detect_delimiter(line));
 
-    let num_samples = examples.len() as u32;
+        if options.no_header {
+            let position = |column: &str| {
+                column.trim().parse::<usize>().map_err(|_| {
+                    format!("Column '{}' must be a position when the CSV has no header", column)
+                })
+            };
+            let input = options.input_column.as_deref().map(position).transpose()?.unwrap_or(0);
+            let output = match &options.output_columns {
+                Some(columns) => columns.iter().map(|c| position(c)).collect::<Result<_, _>>()?,
+                None => vec![1],
+            };
+            let system = options.system_column.as_deref().map(position).transpose()?;
+            let weight = options.weight_column.as_deref().map(position).transpose()?;
+            let name = |i: usize| format!("column {}", i);
+            let mapping = CsvColumnMapping {
+                delimiter,
+                input: name(input),
+                output: output.iter().copied().map(name).collect(),
+                system: system.map(name),
+                weight: weight.map(name),
+                metadata: None,
+            };
+            return Ok(Self { delimiter, input, output, system, weight, metadata: None, mapping });
+        }
 
-    // Calculate lengths (approximate tokens as words * 1.3)
-    let input_lengths: Vec<u32> = examples
-        .iter()
-        .map(|e| (e.input.split_whitespace().count() as f32 * 1.3) as u32)
-        .collect();
+        let raw_headers: Vec<String> = split_csv_line(line, delimiter)
+            .into_iter()
+            .map(|h| h.trim_start_matches('\u{feff}').trim().to_string())
+            .collect();
+        let headers: Vec<String> = raw_headers.iter().map(|h| normalize_header(h)).collect();
+        // Earlier names in a synonym list win over later ones
+        let find = |names: &[&str]| {
+            names
+                .iter()
+                .find_map(|name| headers.iter().position(|h| h == name))
+        };
+        let resolve = |column: &str| {
+            find(&[normalize_header(column).as_str()])
+                .or_else(|| column.trim().parse::<usize>().ok().filter(|i| *i < headers.len()))
+                .ok_or_else(|| format!("CSV has no '{}' column", column))
+        };
+        let missing = |field: &str, names: &[&str]| {
+            format!(
+                "CSV has no {} column (expected one of: {}; found: {})",
+                field,
+                names.join(", "),
+                raw_headers.join(", ")
+            )
+        };
 
-    let output_lengths: Vec<u32> = examples
-        .iter()
-        .map(|e| (e.output.split_whitespace().count() as f32 * 1.3) as u32)
-        .collect();
+        let input = match &options.input_column {
+            Some(column) => resolve(column)?,
+            None => find(INPUT_HEADERS).ok_or_else(|| missing("input", INPUT_HEADERS))?,
+        };
+        let output = match &options.output_columns {
+            Some(columns) if !columns.is_empty() => {
+                columns.iter().map(|c| resolve(c)).collect::<Result<_, _>>()?
+            }
+            _ => vec![find(OUTPUT_HEADERS).ok_or_else(|| missing("output", OUTPUT_HEADERS))?],
+        };
+        let system = match &options.system_column {
+            Some(column) => Some(resolve(column)?),
+            None => find(SYSTEM_HEADERS),
+        };
+        let weight = match &options.weight_column {
+            Some(column) => Some(resolve(column)?),
+            None => find(WEIGHT_HEADERS),
+        };
+        let metadata = find(&[METADATA_HEADER]);
 
-    let total_lengths: Vec<u32> = input_lengths
-        .iter()
-        .zip(output_lengths.iter())
-        .map(|(i, o)| i + o)
-        .collect();
+        let mapping = CsvColumnMapping {
+            delimiter,
+            input: raw_headers[input].clone(),
+            output: output.iter().map(|i| raw_headers[*i].clone()).collect(),
+            system: system.map(|i| raw_headers[i].clone()),
+            weight: weight.map(|i| raw_headers[i].clone()),
+            metadata: metadata.map(|i| raw_headers[i].clone()),
+        };
+        Ok(Self { delimiter, input, output, system, weight, metadata, mapping })
+    }
 
-    let avg_input_length = input_lengths.iter().sum::<u32>() / num_samples;
+    /// Parse a data record, failing on a weight or metadata cell that can't be read
+    fn parse_row(&self, record: &str) -> Result<CsvRow, String> {
+        if record.trim().is_empty() {
+            return Ok(CsvRow::Blank);
+        }
+
+        let cols = split_csv_fields(record, self.delimiter);
+        let last = self.output.iter().copied().chain([self.input]).max().unwrap_or(0);
+        if cols.len() <= last {
+            return Ok(CsvRow::Short(format!(
+                "expected at least {} columns, found {}",
+                last + 1,
+                cols.len()
+            )));
+        }
+
+        let output = self
+            .output
+            .iter()
+            .map(|i| cols[*i].value.as_str())
+            .filter(|value| !value.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let weight = match self.weight.and_then(|i| cols.get(i)).map(|w| w.value.trim()) {
+            // Also accept a decimal comma, as written in semicolon-delimited files
+            Some(w) if !w.is_empty() => Some(
+                w.parse::<f32>()
+                    .or_else(|_| w.replace(',', ".").parse::<f32>())
+                    .map_err(|_| format!("weight '{}' is not a number", w))?,
+            ),
+            _ => None,
+        };
+
+        let metadata = match self.metadata.and_then(|i| cols.get(i)) {
+            Some(field) if !field.value.trim().is_empty() => Some(
+                serde_json::from_str::<HashMap<String, String>>(&field.value)
+                    .map_err(|e| format!("metadata is not a JSON object of strings: {}", e))?,
+            ),
+            _ => None,
+        };
+
+        // An empty quoted cell is an empty system prompt; an unquoted one is no prompt
+        let system = self
+            .system
+            .and_then(|i| cols.get(i))
+            .filter(|field| field.quoted || !field.value.is_empty())
+            .map(|field| field.value.clone());
+
+        Ok(CsvRow::Example(TrainingExample {
+            input: cols[self.input].value.clone(),
+            output,
+            system,
+            metadata,
+            weight,
+        }))
+    }
+}
+
+enum CsvRow {
+    Blank,
+    /// Too few columns for the mapping; reported and skipped
+    Short(String),
+    Example(TrainingExample),
+}
+
+/// Pick the candidate delimiter that occurs most often (outside quotes) in a line
+fn detect_delimiter(line: &str) -> char {
+    let mut counts = [0usize; CSV_DELIMITERS.len()];
+    let mut quoted = false;
+    for c in line.chars() {
+        if c == '"' {
+            quoted = !quoted;
+        } else if !quoted {
+            if let Some(i) = CSV_DELIMITERS.iter().position(|d| *d == c) {
+                counts[i] += 1;
+            }
+        }
+    }
+
+    counts
+        .iter()
+        .enumerate()
+        .filter(|(_, count)| **count > 0)
+        .max_by_key(|(i, count)| (**count, std::cmp::Reverse(*i)))
+        .map(|(i, _)| CSV_DELIMITERS[i])
+        .unwrap_or(',')
+}
+
+/// Groups physical lines into CSV records, so line breaks inside quoted
+/// fields stay part of the field
+struct CsvRecords<I> {
+    lines: I,
+    line: u32,
+}
+
+impl<I: Iterator<Item = std::io::Result<String>>> CsvRecords<I> {
+    /// `lines` must keep their line terminators
+    fn new(lines: I) -> Self {
+        Self { lines, line: 0 }
+    }
+}
+
+impl<I: Iterator<Item = std::io::Result<String>>> Iterator for CsvRecords<I> {
+    /// The record's first line number (1-based) and its text
+    type Item = std::io::Result<(u32, String)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut record = match self.lines.next()? {
+            Ok(line) => line,
+            Err(e) => return Some(Err(e)),
+        };
+        self.line += 1;
+        let start = self.line;
+        // An odd number of quotes so far means a quoted field is still open
+        let mut open = record.matches('"').count() % 2 == 1;
+        while open {
+            match self.lines.next() {
+                Some(Ok(line)) => {
+                    self.line += 1;
+                    open ^= line.matches('"').count() % 2 == 1;
+                    record.push_str(&line);
+                }
+                Some(Err(e)) => return Some(Err(e)),
+                None => break,
+            }
+        }
+
+        let record = record.strip_suffix('\n').unwrap_or(&record);
+        let record = record.strip_suffix('\r').unwrap_or(record);
+        Some(Ok((start, record.to_string())))
+    }
+}
+
+/// Split a CSV record, honoring double-quoted fields (with `""` as an escaped quote)
+fn split_csv_line(line: &str, delimiter: char) -> Vec<String> {
+    split_csv_fields(line, delimiter)
+        .into_iter()
+        .map(|field| field.value)
+        .collect()
+}
+
+struct CsvField {
+    value: String,
+    /// Written in quotes, which tells an empty string apart from a missing value
+    quoted: bool,
+}
+
+fn split_csv_fields(line: &str, delimiter: char) -> Vec<CsvField> {
+    let mut fields = Vec::new();
+    let mut field = CsvField { value: String::new(), quoted: false };
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.value.push('"');
+                chars.next();
+            }
+            '"' => {
+                in_quotes = !in_quotes;
+                field.quoted = true;
+            }
+            c if c == delimiter && !in_quotes => fields.push(std::mem::replace(
+                &mut field,
+                CsvField { value: String::new(), quoted: false },
+            )),
+            c => field.value.push(c),
+        }
+    }
+    fields.push(field);
+
+    fields
+}
+
+// ============ Format Conversion ============
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversionDivergence {
+    pub index: u32,
+    pub field: String,
+    pub expected: Option<String>,
+    pub actual: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversionCheck {
+    pub format: String,
+    /// Every example survived the round trip unchanged
+    pub lossless: bool,
+    pub example_count: u32,
+    pub reconstructed_count: u32,
+    pub first_divergence: Option<ConversionDivergence>,
+}
+
+/// Convert examples to a format and parse them back, reporting the first difference
+#[tauri::command]
+pub async fn verify_conversion(
+    state: State<'_, AppState>,
+    examples: Vec<TrainingExample>,
+    format: String,
+) -> Result<ConversionCheck, String> {
+    let result = verify_conversion_inner(examples, format).await;
+    state.capture("verify_conversion", result).await
+}
+
+async fn verify_conversion_inner(
+    examples: Vec<TrainingExample>,
+    format: String,
+) -> Result<ConversionCheck, String> {
+    let format = format.to_lowercase();
+    let serialized = serialize_examples(&examples, &format)?;
+    let reconstructed = match format.as_str() {
+        "jsonl" => parse_jsonl(&serialized)?,
+        "json" => parse_json(&serialized)?,
+        "csv" => parse_csv(&serialized, &CsvOptions::default())?,
+        other => return Err(format!("Unsupported format: {}", other)),
+    };
+
+    let first_divergence = first_divergence(&examples, &reconstructed);
+    Ok(ConversionCheck {
+        format,
+        lossless: first_divergence.is_none(),
+        example_count: examples.len() as u32,
+        reconstructed_count: reconstructed.len() as u32,
+        first_divergence,
+    })
+}
+
+/// Write examples in a dataset format (CSV gets `system`, `weight` and
+/// `metadata` columns only when used)
+pub fn serialize_examples(examples: &[TrainingExample], format: &str) -> Result<String, String> {
+    match format {
+        "jsonl" => {
+            let mut out = String::new();
+            for example in examples {
+                out.push_str(&serde_json::to_string(example).map_err(|e| e.to_string())?);
+                out.push('\n');
+            }
+            Ok(out)
+        }
+        "json" => serde_json::to_string_pretty(examples).map_err(|e| e.to_string()),
+        "csv" => {
+            let with_system = examples.iter().any(|e| e.system.is_some());
+            let with_weight = examples.iter().any(|e| e.weight.is_some());
+            let with_metadata = examples.iter().any(|e| e.metadata.is_some());
+            let mut header = vec!["input", "output"];
+            if with_system {
+                header.push("system");
+            }
+            if with_weight {
+                header.push("weight");
+            }
+            if with_metadata {
+                header.push(METADATA_HEADER);
+            }
+            let mut out = format!("{}\n", header.join(","));
+            for example in examples {
+                let mut fields = vec![csv_field(&example.input), csv_field(&example.output)];
+                if with_system {
+                    // Quote an empty prompt so it reads back as empty rather than missing
+                    fields.push(match example.system.as_deref() {
+                        Some("") => "\"\"".to_string(),
+                        Some(system) => csv_field(system),
+                        None => String::new(),
+                    });
+                }
+                if with_weight {
+                    fields.push(example.weight.map(|w| w.to_string()).unwrap_or_default());
+                }
+                if with_metadata {
+                    let metadata = match &example.metadata {
+                        Some(metadata) => {
+                            let sorted: BTreeMap<_, _> = metadata.iter().collect();
+                            csv_field(&serde_json::to_string(&sorted).map_err(|e| e.to_string())?)
+                        }
+                        None => String::new(),
+                    };
+                    fields.push(metadata);
+                }
+                out.push_str(&fields.join(","));
+                out.push('\n');
+            }
+            Ok(out)
+        }
+        other => Err(format!("Unsupported format: {}", other)),
+    }
+}
+
+/// Quote a CSV field when it contains a delimiter, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', ';', '\t', '|', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn first_divergence(
+    expected: &[TrainingExample],
+    actual: &[TrainingExample],
+) -> Option<ConversionDivergence> {
+    for index in 0..expected.len().max(actual.len()) {
+        let (Some(e), Some(a)) = (expected.get(index), actual.get(index)) else {
+            let describe = |example: Option<&TrainingExample>| example.map(|x| x.input.clone());
+            return Some(ConversionDivergence {
+                index: index as u32,
+                field: "example".to_string(),
+                expected: describe(expected.get(index)),
+                actual: describe(actual.get(index)),
+            });
+        };
+
+        let metadata = |x: &TrainingExample| {
+            x.metadata.as_ref().map(|m| {
+                let mut entries: Vec<_> = m.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+                entries.sort();
+                entries.join(", ")
+            })
+        };
+        let fields = [
+            ("input", Some(e.input.clone()), Some(a.input.clone())),
+            ("output", Some(e.output.clone()), Some(a.output.clone())),
+            ("system", e.system.clone(), a.system.clone()),
+            ("metadata", metadata(e), metadata(a)),
+            ("weight", e.weight.map(|w| w.to_string()), a.weight.map(|w| w.to_string())),
+        ];
+        if let Some((field, expected, actual)) = fields.into_iter().find(|(_, e, a)| e != a) {
+            return Some(ConversionDivergence {
+                index: index as u32,
+                field: field.to_string(),
+                expected,
+                actual,
+            });
+        }
+    }
+    None
+}
+
+// ============ Remote Datasets ============
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppendResult {
+    pub dataset_id: String,
+    pub appended: u32,
+    /// Row count of the remote dataset after appending
+    pub total_rows: u32,
+}
+
+/// Append examples to an existing Tinker dataset, checking they match its columns
+#[tauri::command]
+pub async fn append_to_remote_dataset(
+    state: State<'_, AppState>,
+    dataset_id: String,
+    examples: Vec<TrainingExample>,
+) -> Result<AppendResult, String> {
+    let result = append_to_remote_dataset_inner(&state, dataset_id, examples).await;
+    state.capture("append_to_remote_dataset", result).await
+}
+
+async fn append_to_remote_dataset_inner(
+    state: &AppState,
+    dataset_id: String,
+    examples: Vec<TrainingExample>,
+) -> Result<AppendResult, String> {
+    if examples.is_empty() {
+        return Err("No examples to append".to_string());
+    }
+
+    let client = state.tinker.lock().await;
+    let dataset = client
+        .get_dataset(&dataset_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    check_dataset_columns(&dataset.columns, &examples)?;
+    check_weights(&examples)?;
+    let weighted = dataset.columns.iter().any(|c| c == "weight");
+
+    let mut jsonl = String::new();
+    for example in &examples {
+        let mut row = serde_json::json!({
+            "input": example.input,
+            "output": example.output,
+        });
+        if let Some(system) = &example.system {
+            row["system"] = Value::String(system.clone());
+        }
+        if weighted || example.weight.is_some() {
+            row["weight"] = serde_json::json!(example.weight());
+        }
+        jsonl.push_str(&row.to_string());
+        jsonl.push('\n');
+    }
+
+    let response = client
+        .append_to_dataset(&dataset_id, jsonl.into_bytes(), "append.jsonl")
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(AppendResult {
+        dataset_id,
+        appended: examples.len() as u32,
+        total_rows: response.row_count,
+    })
+}
+
+/// Check new examples only use the columns of an existing dataset and fill
+/// every column it requires (an empty column list means the schema is unknown)
+fn check_dataset_columns(columns: &[String], examples: &[TrainingExample]) -> Result<(), String> {
+    if columns.is_empty() {
+        return Ok(());
+    }
+
+    let has = |name: &str| columns.iter().any(|c| c == name);
+    for required in ["input", "output"] {
+        if !has(required) {
+            return Err(format!(
+                "Dataset columns ({}) don't include \"{}\"",
+                columns.join(", "),
+                required
+            ));
+        }
+    }
+
+    if let Some(extra) = columns
+        .iter()
+        .find(|c| !matches!(c.as_str(), "input" | "output" | "system" | "weight"))
+    {
+        return Err(format!("Dataset expects a \"{}\" column the examples don't have", extra));
+    }
+
+    if !has("system") && examples.iter().any(|e| e.system.is_some()) {
+        return Err("Dataset has no \"system\" column but some examples set one".to_string());
+    }
+
+    if !has("weight") && examples.iter().any(|e| e.weight.is_some()) {
+        return Err("Dataset has no \"weight\" column but some examples set one".to_string());
+    }
+
+    Ok(())
+}
+
+// ============ Dataset Registry ============
+
+/// Approximate token counts for one example, cached per registered dataset
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ExampleTokens {
+    pub input: u32,
+    pub output: u32,
+    pub system: u32,
+}
+
+impl ExampleTokens {
+    pub fn of(example: &TrainingExample) -> Self {
+        Self {
+            input: estimate_tokens(&example.input),
+            output: estimate_tokens(&example.output),
+            system: example.system.as_deref().map(estimate_tokens).unwrap_or(0),
+        }
+    }
+
+    /// Tokens for the full training sequence (system + input + output)
+    pub fn total(&self) -> u32 {
+        self.input + self.output + self.system
+    }
+}
+
+/// Versions kept per registered dataset before the oldest are dropped
+const MAX_DATASET_VERSIONS: usize = 20;
+/// Approximate text size kept across a dataset's versions before the oldest are dropped
+const MAX_VERSION_HISTORY_BYTES: usize = 50 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatasetVersionInfo {
+    pub version: u32,
+    /// Version this one was derived from; `None` for the ingested original
+    pub parent: Option<u32>,
+    /// What produced this version ("ingest", "dedup", "filter", ...)
+    pub operation: String,
+    pub created_at: String,
+    pub example_count: u32,
+    pub size_bytes: usize,
+}
+
+struct DatasetVersion {
+    info: DatasetVersionInfo,
+    examples: Vec<TrainingExample>,
+}
+
+/// A dataset held in memory after ingest, with token counts computed once and
+/// a capped history of full snapshots for undoing curation steps
+pub struct RegisteredDataset {
+    /// Oldest first; always contains the current version
+    versions: Vec<DatasetVersion>,
+    current: u32,
+    next_version: u32,
+    token_counts: Option<Vec<ExampleTokens>>,
+    /// When a command last read or edited the dataset, for registry eviction
+    last_used: Instant,
+}
+
+impl RegisteredDataset {
+    pub fn new(examples: Vec<TrainingExample>) -> Self {
+        let token_counts = Some(examples.iter().map(ExampleTokens::of).collect());
+        let mut dataset = Self {
+            versions: Vec::new(),
+            current: 1,
+            next_version: 1,
+            token_counts,
+            last_used: Instant::now(),
+        };
+        dataset.push_version(examples, None, "ingest");
+        dataset
+    }
+
+    pub fn examples(&self) -> &[TrainingExample] {
+        &self.current_version().examples
+    }
+
+    /// Cached token counts, recomputed if a mutation invalidated them
+    pub fn token_counts(&mut self) -> &[ExampleTokens] {
+        let examples = &self
+            .versions
+            .iter()
+            .find(|v| v.info.version == self.current)
+            .expect("current version is never pruned")
+            .examples;
+        self.token_counts
+            .get_or_insert_with(|| examples.iter().map(ExampleTokens::of).collect())
+    }
+
+    /// Record edited examples (after dedup, filtering, merging, ...) as a new
+    /// version derived from the current one; returns the new version number
+    pub fn replace_examples(&mut self, examples: Vec<TrainingExample>, operation: &str) -> u32 {
+        let parent = self.current;
+        self.token_counts = None;
+        self.push_version(examples, Some(parent), operation)
+    }
+
+    /// Make an earlier (or, after a revert, later) version current again
+    pub fn revert(&mut self, version: u32) -> Result<(), String> {
+        if !self.versions.iter().any(|v| v.info.version == version) {
+            return Err(format!("Version {} is not in the dataset's history", version));
+        }
+        if version != self.current {
+            self.current = version;
+            self.token_counts = None;
+        }
+        Ok(())
+    }
+
+    pub fn current_version_number(&self) -> u32 {
+        self.current
+    }
+
+    pub fn last_used(&self) -> Instant {
+        self.last_used
+    }
+
+    /// Approximate text size held across all versions
+    pub fn size_bytes(&self) -> usize {
+        self.versions.iter().map(|v| v.info.size_bytes).sum()
+    }
+
+    pub fn versions(&self) -> Vec<DatasetVersionInfo> {
+        self.versions.iter().map(|v| v.info.clone()).collect()
+    }
+
+    fn current_version(&self) -> &DatasetVersion {
+        self.versions
+            .iter()
+            .find(|v| v.info.version == self.current)
+            .expect("current version is never pruned")
+    }
+
+    fn push_version(
+        &mut self,
+        examples: Vec<TrainingExample>,
+        parent: Option<u32>,
+        operation: &str,
+    ) -> u32 {
+        let version = self.next_version;
+        self.next_version += 1;
+        self.versions.push(DatasetVersion {
+            info: DatasetVersionInfo {
+                version,
+                parent,
+                operation: operation.to_string(),
+                created_at: chrono::Utc::now().to_rfc3339(),
+                example_count: examples.len() as u32,
+                size_bytes: examples_size(&examples),
+            },
+            examples,
+        });
+        self.current = version;
+        self.prune();
+        version
+    }
+
+    /// Drop the oldest non-current versions until the history fits its caps
+    fn prune(&mut self) {
+        let total = |versions: &[DatasetVersion]| -> usize {
+            versions.iter().map(|v| v.info.size_bytes).sum()
+        };
+        while self.versions.len() > MAX_DATASET_VERSIONS
+            || (self.versions.len() > 1 && total(&self.versions) > MAX_VERSION_HISTORY_BYTES)
+        {
+            let Some(oldest) = self.versions.iter().position(|v| v.info.version != self.current)
+            else {
+                break;
+            };
+            self.versions.remove(oldest);
+        }
+    }
+}
+
+/// Approximate in-memory text size of a set of examples
+fn examples_size(examples: &[TrainingExample]) -> usize {
+    examples
+        .iter()
+        .map(|e| e.input.len() + e.output.len() + e.system.as_ref().map_or(0, String::len))
+        .sum()
+}
+
+/// Use the given examples, or a registered dataset's examples and cached token counts
+async fn dataset_with_tokens(
+    state: &AppState,
+    examples: Option<Vec<TrainingExample>>,
+    dataset_id: Option<String>,
+) -> Result<(Vec<TrainingExample>, Vec<ExampleTokens>), String> {
+    match (examples, dataset_id) {
+        (Some(examples), _) => {
+            let tokens = examples.iter().map(ExampleTokens::of).collect();
+            Ok((examples, tokens))
+        }
+        (None, Some(id)) => {
+            let mut datasets = state.datasets.lock().await;
+            let dataset = registered(&mut datasets, &id)?;
+            let tokens = dataset.token_counts().to_vec();
+            Ok((dataset.examples().to_vec(), tokens))
+        }
+        (None, None) => Err("Either examples or dataset_id is required".to_string()),
+    }
+}
+
+/// Use the given examples, or a registered dataset's current examples
+async fn dataset_examples(
+    state: &AppState,
+    examples: Option<Vec<TrainingExample>>,
+    dataset_id: Option<&str>,
+) -> Result<Vec<TrainingExample>, String> {
+    match (examples, dataset_id) {
+        (Some(examples), _) => Ok(examples),
+        (None, Some(id)) => {
+            let mut datasets = state.datasets.lock().await;
+            Ok(registered(&mut datasets, id)?.examples().to_vec())
+        }
+        (None, None) => Err("Either examples or dataset_id is required".to_string()),
+    }
+}
+
+/// A registered dataset's current examples, if `dataset_id` names one
+pub async fn registered_examples(
+    state: &AppState,
+    dataset_id: &str,
+) -> Option<Vec<TrainingExample>> {
+    let mut datasets = state.datasets.lock().await;
+    registered(&mut datasets, dataset_id)
+        .ok()
+        .map(|dataset| dataset.examples().to_vec())
+}
+
+/// Record a curation command's output as a new version of a registered dataset
+async fn record_dataset_edit(
+    state: &AppState,
+    dataset_id: &str,
+    examples: Vec<TrainingExample>,
+    operation: &str,
+) -> Result<u32, String> {
+    let mut datasets = state.datasets.lock().await;
+    Ok(registered(&mut datasets, dataset_id)?.replace_examples(examples, operation))
+}
+
+/// Look up a registered dataset, marking it as recently used
+fn registered<'a>(
+    datasets: &'a mut HashMap<String, RegisteredDataset>,
+    dataset_id: &str,
+) -> Result<&'a mut RegisteredDataset, String> {
+    let dataset = datasets
+        .get_mut(dataset_id)
+        .ok_or_else(|| format!("Dataset not found: {}", dataset_id))?;
+    dataset.last_used = Instant::now();
+    Ok(dataset)
+}
+
+/// Per-example token counts for a registered dataset
+#[tauri::command]
+pub async fn get_token_counts(
+    state: State<'_, AppState>,
+    dataset_id: String,
+) -> Result<Vec<ExampleTokens>, String> {
+    let result = get_token_counts_inner(&state, dataset_id).await;
+    state.capture("get_token_counts", result).await
+}
+
+async fn get_token_counts_inner(
+    state: &AppState,
+    dataset_id: String,
+) -> Result<Vec<ExampleTokens>, String> {
+    let mut datasets = state.datasets.lock().await;
+    let dataset = registered(&mut datasets, &dataset_id)?;
+    Ok(dataset.token_counts().to_vec())
+}
+
+/// Replace a registered dataset's examples with an edited version (e.g. after
+/// dedup or filtering), invalidating its cached token counts
+///
+/// The edit is recorded as a new version named by `operation`, so it can be
+/// undone with `revert_dataset`.
+#[tauri::command]
+pub async fn update_dataset(
+    state: State<'_, AppState>,
+    dataset_id: String,
+    examples: Vec<TrainingExample>,
+    operation: Option<String>,
+) -> Result<u32, String> {
+    let result = update_dataset_inner(&state, dataset_id, examples, operation).await;
+    state.capture("update_dataset", result).await
+}
+
+async fn update_dataset_inner(
+    state: &AppState,
+    dataset_id: String,
+    examples: Vec<TrainingExample>,
+    operation: Option<String>,
+) -> Result<u32, String> {
+    let mut datasets = state.datasets.lock().await;
+    let dataset = registered(&mut datasets, &dataset_id)?;
+    check_weights(&examples)?;
+    let count = examples.len() as u32;
+    dataset.replace_examples(examples, operation.as_deref().unwrap_or("edit"));
+    Ok(count)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatasetHistory {
+    pub dataset_id: String,
+    pub current_version: u32,
+    /// Versions still held, oldest first
+    pub versions: Vec<DatasetVersionInfo>,
+}
+
+/// Version history of a registered dataset
+#[tauri::command]
+pub async fn list_dataset_versions(
+    state: State<'_, AppState>,
+    dataset_id: String,
+) -> Result<DatasetHistory, String> {
+    let result = list_dataset_versions_inner(&state, dataset_id).await;
+    state.capture("list_dataset_versions", result).await
+}
+
+async fn list_dataset_versions_inner(
+    state: &AppState,
+    dataset_id: String,
+) -> Result<DatasetHistory, String> {
+    let mut datasets = state.datasets.lock().await;
+    let dataset = registered(&mut datasets, &dataset_id)?;
+    Ok(DatasetHistory {
+        current_version: dataset.current_version_number(),
+        versions: dataset.versions(),
+        dataset_id,
+    })
+}
+
+/// Make a previous version of a registered dataset current (undo), or a later
+/// one after reverting (redo); returns that version's examples
+#[tauri::command]
+pub async fn revert_dataset(
+    state: State<'_, AppState>,
+    dataset_id: String,
+    version: u32,
+) -> Result<Vec<TrainingExample>, String> {
+    let result = revert_dataset_inner(&state, dataset_id, version).await;
+    state.capture("revert_dataset", result).await
+}
+
+async fn revert_dataset_inner(
+    state: &AppState,
+    dataset_id: String,
+    version: u32,
+) -> Result<Vec<TrainingExample>, String> {
+    let mut datasets = state.datasets.lock().await;
+    let dataset = registered(&mut datasets, &dataset_id)?;
+    dataset.revert(version)?;
+    Ok(dataset.examples().to_vec())
+}
+
+/// Drop a registered dataset and its version history to free memory
+#[tauri::command]
+pub async fn remove_dataset(
+    state: State<'_, AppState>,
+    dataset_id: String,
+) -> Result<bool, String> {
+    Ok(state.remove_dataset(&dataset_id).await)
+}
+
+// ============ Data Preview ============
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataPreview {
+    pub samples: Vec<TrainingExample>,
+    pub total_count: u32,
+}
+
+/// Preview dataset (first N examples)
+#[tauri::command]
+pub async fn preview_dataset(
+    examples: Vec<TrainingExample>,
+    limit: Option<u32>,
+) -> Result<DataPreview, String> {
+    let limit = limit.unwrap_or(10) as usize;
+    let total = examples.len() as u32;
+
+    Ok(DataPreview {
+        samples: examples.into_iter().take(limit).collect(),
+        total_count: total,
+    })
+}
+
+// ============ File Preview ============
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilePreview {
+    pub samples: Vec<TrainingExample>,
+    pub offset: u32,
+    /// Non-blank rows in the whole file (counted without parsing them, so CSV
+    /// rows that would be skipped on upload are included)
+    pub total_count: u32,
+    /// Which CSV columns became input/output/system, for the user to confirm
+    pub csv_columns: Option<CsvColumnMapping>,
+    /// CSV rows in the previewed window that couldn't be read as examples
+    pub skipped_rows: Vec<SkippedRow>,
+}
+
+/// Preview a window of rows straight from a dataset file
+///
+/// JSONL and CSV files are streamed line by line, so only the requested
+/// rows are parsed. JSON arrays have to be parsed whole.
+#[tauri::command]
+pub async fn preview_file(
+    state: State<'_, AppState>,
+    file_path: String,
+    format: Option<String>,
+    offset: Option<u32>,
+    limit: Option<u32>,
+    csv_options: Option<CsvOptions>,
+) -> Result<FilePreview, String> {
+    let result = preview_file_inner(file_path, format, offset, limit, csv_options).await;
+    state.capture("preview_file", result).await
+}
+
+async fn preview_file_inner(
+    file_path: String,
+    format: Option<String>,
+    offset: Option<u32>,
+    limit: Option<u32>,
+    csv_options: Option<CsvOptions>,
+) -> Result<FilePreview, String> {
+    let offset = offset.unwrap_or(0) as usize;
+    let limit = limit.unwrap_or(10) as usize;
+
+    let mut csv_columns = None;
+    let mut skipped_rows = Vec::new();
+    let (samples, total) = match detect_format(&file_path, format).as_str() {
+        "jsonl" => {
+            let mut samples = Vec::new();
+            let mut total = 0;
+            for line in open_lines(&file_path)? {
+                let line = line.map_err(|e| format!("Failed to read file: {}", e))?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                if total >= offset && samples.len() < limit {
+                    let example = serde_json::from_str::<TrainingExample>(&line)
+                        .map_err(|e| format!("Failed to parse JSONL row {}: {}", total + 1, e))?;
+                    samples.push(example);
+                }
+                total += 1;
+            }
+            (samples, total)
+        }
+        "csv" => {
+            let options = csv_options.unwrap_or_default();
+            let mut records = open_csv_records(&file_path)?;
+            let first = records
+                .next()
+                .ok_or("Empty CSV file")?
+                .map_err(|e| format!("Failed to read file: {}", e))?;
+            let columns = CsvColumns::from_first_line(&first.1, &options)?;
+
+            let mut samples = Vec::new();
+            let mut total = 0;
+            let first_row = options.no_header.then_some(Ok(first));
+            for record in first_row.into_iter().chain(records) {
+                let (line, record) =
+                    record.map_err(|e| format!("Failed to read file: {}", e))?;
+                if record.trim().is_empty() {
+                    continue;
+                }
+                if total >= offset && total < offset + limit {
+                    let row = columns
+                        .parse_row(&record)
+                        .map_err(|e| format!("Line {}: {}", line, e))?;
+                    match row {
+                        CsvRow::Example(example) => samples.push(example),
+                        CsvRow::Blank => {}
+                        CsvRow::Short(reason) => skipped_rows.push(SkippedRow { line, reason }),
+                    }
+                }
+                total += 1;
+            }
+            csv_columns = Some(columns.mapping);
+            (samples, total)
+        }
+        "json" => {
+            let content = std::fs::read_to_string(&file_path)
+                .map_err(|e| format!("Failed to read file: {}", e))?;
+            let examples = parse_json(&content)?;
+            let total = examples.len();
+            (examples.into_iter().skip(offset).take(limit).collect(), total)
+        }
+        other => return Err(format!("Unsupported format: {}", other)),
+    };
+
+    Ok(FilePreview {
+        samples,
+        offset: offset as u32,
+        total_count: total as u32,
+        csv_columns,
+        skipped_rows,
+    })
+}
+
+fn open_lines(file_path: &str) -> Result<std::io::Lines<BufReader<File>>, String> {
+    let file = File::open(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+    Ok(BufReader::new(file).lines())
+}
+
+fn open_csv_records(
+    file_path: &str,
+) -> Result<CsvRecords<impl Iterator<Item = std::io::Result<String>>>, String> {
+    let file = File::open(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let mut reader = BufReader::new(file);
+    let lines = std::iter::from_fn(move || {
+        let mut line = String::new();
+        match reader.read_line(&mut line) {
+            Ok(0) => None,
+            Ok(_) => Some(Ok(line)),
+            Err(e) => Some(Err(e)),
+        }
+    });
+    Ok(CsvRecords::new(lines))
+}
+
+// ============ Dataset Stats ============
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatasetStats {
+    pub num_samples: u32,
+    pub avg_input_length: u32,
+    pub avg_output_length: u32,
+    pub avg_tokens_per_sample: u32,
+    pub max_tokens: u32,
+    pub min_tokens: u32,
+    pub has_system_prompts: bool,
+    pub unique_system_prompts: u32,
+}
+
+/// Get statistics about a dataset
+#[tauri::command]
+pub async fn get_dataset_stats(
+    state: State<'_, AppState>,
+    examples: Option<Vec<TrainingExample>>,
+    dataset_id: Option<String>,
+) -> Result<DatasetStats, String> {
+    let result = get_dataset_stats_inner(&state, examples, dataset_id).await;
+    state.capture("get_dataset_stats", result).await
+}
+
+async fn get_dataset_stats_inner(
+    state: &AppState,
+    examples: Option<Vec<TrainingExample>>,
+    dataset_id: Option<String>,
+) -> Result<DatasetStats, String> {
+    let (examples, tokens) = dataset_with_tokens(state, examples, dataset_id).await?;
+    if examples.is_empty() {
+        return Err("Dataset is empty".to_string());
+    }
+
+    let num_samples = examples.len() as u32;
+
+    let input_lengths: Vec<u32> = tokens.iter().map(|t| t.input).collect();
+    let output_lengths: Vec<u32> = tokens.iter().map(|t| t.output).collect();
+
+    let total_lengths: Vec<u32> = input_lengths
+        .iter()
+        .zip(output_lengths.iter())
+        .map(|(i, o)| i + o)
+        .collect();
+
+    let avg_input_length = input_lengths.iter().sum::<u32>() / num_samples;
     let avg_output_length = output_lengths.iter().sum::<u32>() / num_samples;
     let avg_tokens = total_lengths.iter().sum::<u32>() / num_samples;
     let max_tokens = *total_lengths.iter().max().unwrap_or(&0);
     let min_tokens = *total_lengths.iter().min().unwrap_or(&0);
 
-    let system_prompts: std::collections::HashSet<_> = examples
+    let system_prompts: HashSet<_> = examples
         .iter()
         .filter_map(|e| e.system.as_ref())
         .collect();
@@ -283,3 +2119,822 @@ pub async fn get_dataset_stats(
         unique_system_prompts: system_prompts.len() as u32,
     })
 }
+
+/// Approximate token count (words * 1.3)
+pub fn estimate_tokens(text: &str) -> u32 {
+    (text.split_whitespace().count() as f32 * 1.3) as u32
+}
+
+/// Nearest-rank percentile of an ascending-sorted slice (`p` in 0-100)
+pub fn percentile(sorted: &[u32], p: f64) -> u32 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+// ============ Length Balance ============
+
+/// Output:input token ratio below which a long prompt's answer counts as degenerate
+const MIN_OUTPUT_INPUT_RATIO: f64 = 0.05;
+/// Output:input token ratio above which a short prompt counts as degenerate
+const MAX_OUTPUT_INPUT_RATIO: f64 = 50.0;
+/// Inputs shorter than this are too short to judge a terse answer against
+const MIN_INPUT_TOKENS_FOR_RATIO: u32 = 40;
+/// Outputs shorter than this are too short to call a prompt trivial
+const MIN_OUTPUT_TOKENS_FOR_RATIO: u32 = 200;
+/// Fraction of degenerate examples at which the dataset is flagged
+const IMBALANCE_THRESHOLD: f64 = 0.2;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LengthBalanceReport {
+    /// Output:input token ratio percentiles
+    pub p10_ratio: f64,
+    pub median_ratio: f64,
+    pub p90_ratio: f64,
+    /// Long prompts answered in a few tokens
+    pub short_output_indices: Vec<u32>,
+    /// Near-empty prompts with long answers
+    pub short_input_indices: Vec<u32>,
+    pub flagged_fraction: f64,
+    pub is_imbalanced: bool,
+    pub warning: Option<ValidationIssue>,
+}
+
+/// Flag examples whose output length is degenerate relative to their input
+///
+/// Pass either `examples` or the `dataset_id` of a registered dataset, whose
+/// cached token counts are used.
+#[tauri::command]
+pub async fn check_length_balance(
+    state: State<'_, AppState>,
+    examples: Option<Vec<TrainingExample>>,
+    dataset_id: Option<String>,
+) -> Result<LengthBalanceReport, String> {
+    let result = check_length_balance_inner(&state, examples, dataset_id).await;
+    state.capture("check_length_balance", result).await
+}
+
+async fn check_length_balance_inner(
+    state: &AppState,
+    examples: Option<Vec<TrainingExample>>,
+    dataset_id: Option<String>,
+) -> Result<LengthBalanceReport, String> {
+    let (examples, tokens) = dataset_with_tokens(state, examples, dataset_id).await?;
+    if examples.is_empty() {
+        return Err("Dataset is empty".to_string());
+    }
+
+    let mut ratios = Vec::with_capacity(examples.len());
+    let mut short_output_indices = Vec::new();
+    let mut short_input_indices = Vec::new();
+
+    for (index, counts) in tokens.iter().enumerate() {
+        let (input, output) = (counts.input, counts.output);
+        let ratio = (output + 1) as f64 / (input + 1) as f64;
+        ratios.push(ratio);
+
+        if ratio < MIN_OUTPUT_INPUT_RATIO && input >= MIN_INPUT_TOKENS_FOR_RATIO {
+            short_output_indices.push(index as u32);
+        } else if ratio > MAX_OUTPUT_INPUT_RATIO && output >= MIN_OUTPUT_TOKENS_FOR_RATIO {
+            short_input_indices.push(index as u32);
+        }
+    }
+
+    ratios.sort_by(|a, b| a.total_cmp(b));
+    let ratio_at = |p: f64| {
+        let rank = ((p / 100.0) * ratios.len() as f64).ceil() as usize;
+        ratios[rank.clamp(1, ratios.len()) - 1]
+    };
+
+    let flagged = short_output_indices.len() + short_input_indices.len();
+    let flagged_fraction = flagged as f64 / examples.len() as f64;
+    let is_imbalanced = flagged_fraction >= IMBALANCE_THRESHOLD;
+
+    let warning = is_imbalanced.then(|| ValidationIssue {
+        severity: IssueSeverity::Warning,
+        category: "length_balance".to_string(),
+        description: format!(
+            "{:.0}% of examples have degenerate output:input lengths \
+             ({} terse answers to long prompts, {} long answers to near-empty prompts)",
+            flagged_fraction * 100.0,
+            short_output_indices.len(),
+            short_input_indices.len()
+        ),
+        affected_count: Some(flagged as u32),
+    });
+
+    Ok(LengthBalanceReport {
+        p10_ratio: ratio_at(10.0),
+        median_ratio: ratio_at(50.0),
+        p90_ratio: ratio_at(90.0),
+        short_output_indices,
+        short_input_indices,
+        flagged_fraction,
+        is_imbalanced,
+        warning,
+    })
+}
+
+// ============ System Prompts ============
+
+/// Distinct prompts per example above which a dataset has "too many" system prompts
+const MAX_SYSTEM_PROMPTS_PER_EXAMPLE: f64 = 0.05;
+/// Never flag datasets with this few distinct system prompts
+const MIN_FLAGGED_SYSTEM_PROMPTS: usize = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemPromptGroup {
+    /// `None` groups the examples without a system prompt
+    pub prompt: Option<String>,
+    pub count: u32,
+    pub percent: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemPromptAnalysis {
+    /// Groups ordered by example count, largest first
+    pub groups: Vec<SystemPromptGroup>,
+    pub distinct_count: u32,
+    pub too_many_prompts: bool,
+    /// Sets of prompts that are identical apart from whitespace
+    pub near_duplicates: Vec<Vec<String>>,
+    pub suggestions: Vec<String>,
+}
+
+/// Group examples by system prompt and flag inconsistent usage
+#[tauri::command]
+pub async fn analyze_system_prompts(
+    state: State<'_, AppState>,
+    examples: Vec<TrainingExample>,
+) -> Result<SystemPromptAnalysis, String> {
+    let result = analyze_system_prompts_inner(examples).await;
+    state.capture("analyze_system_prompts", result).await
+}
+
+async fn analyze_system_prompts_inner(
+    examples: Vec<TrainingExample>,
+) -> Result<SystemPromptAnalysis, String> {
+    if examples.is_empty() {
+        return Err("Dataset is empty".to_string());
+    }
+
+    let mut counts: HashMap<Option<&str>, u32> = HashMap::new();
+    for example in &examples {
+        *counts.entry(example.system.as_deref()).or_insert(0) += 1;
+    }
+
+    let total = examples.len() as f32;
+    let mut groups: Vec<SystemPromptGroup> = counts
+        .iter()
+        .map(|(prompt, count)| SystemPromptGroup {
+            prompt: prompt.map(str::to_string),
+            count: *count,
+            percent: *count as f32 / total * 100.0,
+        })
+        .collect();
+    groups.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.prompt.cmp(&b.prompt)));
+
+    let prompts: Vec<&str> = counts.keys().filter_map(|p| *p).collect();
+    let distinct_count = prompts.len();
+
+    let mut by_collapsed: HashMap<String, Vec<String>> = HashMap::new();
+    for prompt in &prompts {
+        let collapsed = prompt.split_whitespace().collect::<Vec<_>>().join(" ");
+        by_collapsed.entry(collapsed).or_default().push(prompt.to_string());
+    }
+    let mut near_duplicates: Vec<Vec<String>> = by_collapsed
+        .into_values()
+        .filter(|variants| variants.len() > 1)
+        .map(|mut variants| {
+            variants.sort();
+            variants
+        })
+        .collect();
+    near_duplicates.sort();
+
+    let too_many_prompts = distinct_count >= MIN_FLAGGED_SYSTEM_PROMPTS
+        && distinct_count as f64 > examples.len() as f64 * MAX_SYSTEM_PROMPTS_PER_EXAMPLE;
+
+    let mut suggestions = Vec::new();
+    if !near_duplicates.is_empty() {
+        suggestions.push(format!(
+            "Normalize whitespace to merge {} set(s) of near-identical system prompts",
+            near_duplicates.len()
+        ));
+    }
+    if too_many_prompts {
+        suggestions.push(format!(
+            "{} distinct system prompts across {} examples; consolidate into a few shared prompts",
+            distinct_count,
+            examples.len()
+        ));
+    }
+    let without = counts.get(&None).copied().unwrap_or(0);
+    if without > 0 && distinct_count > 0 {
+        suggestions.push(format!(
+            "{} examples have no system prompt while others do; add one for consistency",
+            without
+        ));
+    }
+
+    Ok(SystemPromptAnalysis {
+        groups,
+        distinct_count: distinct_count as u32,
+        too_many_prompts,
+        near_duplicates,
+        suggestions,
+    })
+}
+
+/// Default share of examples a system prompt must cover to become the global one
+const DEFAULT_COMMON_SYSTEM_THRESHOLD: f32 = 0.8;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommonSystemExtraction {
+    /// The prompt folded out of the examples, if one met the threshold
+    pub global_system: Option<String>,
+    /// Dataset with the global prompt removed from the examples that carried it
+    pub examples: Vec<TrainingExample>,
+    /// Examples the prompt was removed from. Only these should get it back;
+    /// examples that never had a system prompt are not listed
+    pub extracted_indices: Vec<u32>,
+    pub extracted_count: u32,
+    /// Fraction of examples that carried the global prompt
+    pub coverage: f32,
+    /// Version recorded for the edit when run against a registered dataset
+    pub version: Option<u32>,
+}
+
+/// Fold a system prompt shared by most examples into a single global prompt
+///
+/// Pass either `examples` or the `dataset_id` of a registered dataset; with a
+/// `dataset_id` the result is recorded as a new version of that dataset.
+#[tauri::command]
+pub async fn extract_common_system(
+    state: State<'_, AppState>,
+    examples: Option<Vec<TrainingExample>>,
+    dataset_id: Option<String>,
+    threshold: Option<f32>,
+) -> Result<CommonSystemExtraction, String> {
+    let result = extract_common_system_inner(&state, examples, dataset_id, threshold).await;
+    state.capture("extract_common_system", result).await
+}
+
+async fn extract_common_system_inner(
+    state: &AppState,
+    examples: Option<Vec<TrainingExample>>,
+    dataset_id: Option<String>,
+    threshold: Option<f32>,
+) -> Result<CommonSystemExtraction, String> {
+    let examples = dataset_examples(state, examples, dataset_id.as_deref()).await?;
+    if examples.is_empty() {
+        return Err("Dataset is empty".to_string());
+    }
+    let threshold = threshold.unwrap_or(DEFAULT_COMMON_SYSTEM_THRESHOLD);
+    if !(threshold > 0.0 && threshold <= 1.0) {
+        return Err(format!("Threshold must be in (0, 1]: {}", threshold));
+    }
+
+    // Count and first index per prompt; ties go to the prompt seen first
+    let mut counts: HashMap<&str, (u32, usize)> = HashMap::new();
+    for (index, system) in examples
+        .iter()
+        .enumerate()
+        .filter_map(|(i, e)| Some((i, e.system.as_deref()?)))
+    {
+        counts.entry(system).or_insert((0, index)).0 += 1;
+    }
+
+    let total = examples.len() as f32;
+    let common = counts
+        .into_iter()
+        .max_by_key(|(_, (count, first))| (*count, std::cmp::Reverse(*first)))
+        .filter(|(_, (count, _))| *count as f32 / total >= threshold)
+        .map(|(prompt, (count, _))| (prompt.to_string(), count));
+
+    let Some((global_system, extracted_count)) = common else {
+        return Ok(CommonSystemExtraction {
+            global_system: None,
+            examples,
+            extracted_indices: Vec::new(),
+            extracted_count: 0,
+            coverage: 0.0,
+            version: None,
+        });
+    };
+
+    let mut extracted_indices = Vec::new();
+    let examples: Vec<TrainingExample> = examples
+        .into_iter()
+        .enumerate()
+        .map(|(index, mut example)| {
+            if example.system.as_deref() == Some(global_system.as_str()) {
+                example.system = None;
+                extracted_indices.push(index as u32);
+            }
+            example
+        })
+        .collect();
+
+    let version = match &dataset_id {
+        Some(id) => {
+            Some(record_dataset_edit(state, id, examples.clone(), "extract_system").await?)
+        }
+        None => None,
+    };
+
+    Ok(CommonSystemExtraction {
+        global_system: Some(global_system),
+        examples,
+        extracted_indices,
+        extracted_count,
+        coverage: extracted_count as f32 / total,
+        version,
+    })
+}
+
+// ============ Split Leakage ============
+
+/// Word-set Jaccard similarity at which two texts count as near-duplicates
+const NEAR_DUPLICATE_SIMILARITY: f64 = 0.9;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LeakKind {
+    Exact,
+    /// Equal after lowercasing and collapsing whitespace
+    Normalized,
+    NearDuplicate,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SplitOverlap {
+    pub eval_index: u32,
+    pub train_index: u32,
+    /// "input" or "output"
+    pub field: String,
+    pub kind: LeakKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LeakageReport {
+    /// Eval inputs that (near-)duplicate a train input
+    pub overlaps: Vec<SplitOverlap>,
+    /// Eval outputs matching a train output while the inputs differ. Common for
+    /// short answers, so these are informational and never count as leaks
+    pub output_overlaps: Vec<SplitOverlap>,
+    pub leaked_eval_indices: Vec<u32>,
+    /// Fraction of eval examples whose input overlaps the train split
+    pub leakage_rate: f32,
+    /// Eval split with leaked examples removed, when requested
+    pub cleaned_eval: Option<Vec<TrainingExample>>,
+    /// Version recorded for the cleaned eval split when it is a registered dataset
+    pub cleaned_version: Option<u32>,
+}
+
+/// Find eval examples whose input (near-)duplicates a train example, noting
+/// output-only matches separately
+///
+/// Each split is given either as examples or as a registered dataset id. When
+/// leaked examples are removed from a registered eval split, the cleaned split
+/// is recorded as a new version of it.
+#[tauri::command]
+pub async fn check_split_leakage(
+    state: State<'_, AppState>,
+    train: Option<Vec<TrainingExample>>,
+    eval: Option<Vec<TrainingExample>>,
+    train_dataset_id: Option<String>,
+    eval_dataset_id: Option<String>,
+    remove_leaked: Option<bool>,
+) -> Result<LeakageReport, String> {
+    let result = check_split_leakage_inner(
+        &state,
+        train,
+        eval,
+        train_dataset_id,
+        eval_dataset_id,
+        remove_leaked,
+    )
+    .await;
+    state.capture("check_split_leakage", result).await
+}
+
+async fn check_split_leakage_inner(
+    state: &AppState,
+    train: Option<Vec<TrainingExample>>,
+    eval: Option<Vec<TrainingExample>>,
+    train_dataset_id: Option<String>,
+    eval_dataset_id: Option<String>,
+    remove_leaked: Option<bool>,
+) -> Result<LeakageReport, String> {
+    let train = dataset_examples(state, train, train_dataset_id.as_deref()).await?;
+    let eval = dataset_examples(state, eval, eval_dataset_id.as_deref()).await?;
+    if eval.is_empty() {
+        return Err("Eval split is empty".to_string());
+    }
+
+    let mut overlaps = Vec::new();
+    for field in ["input", "output"] {
+        let text = |e: &TrainingExample| match field {
+            "input" => e.input.clone(),
+            _ => e.output.clone(),
+        };
+        let train_texts: Vec<LeakText> = train.iter().map(|e| LeakText::new(text(e))).collect();
+        let normalized: HashMap<&str, usize> = train_texts
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(i, t)| (t.normalized.as_str(), i))
+            .collect();
+
+        for (eval_index, example) in eval.iter().enumerate() {
+            let candidate = LeakText::new(text(example));
+            if candidate.normalized.is_empty() {
+                continue;
+            }
+
+            let found = match normalized.get(candidate.normalized.as_str()) {
+                Some(&i) if train_texts[i].raw == candidate.raw => Some((i, LeakKind::Exact)),
+                Some(&i) => Some((i, LeakKind::Normalized)),
+                None => train_texts
+                    .iter()
+                    .position(|t| t.similarity(&candidate) >= NEAR_DUPLICATE_SIMILARITY)
+                    .map(|i| (i, LeakKind::NearDuplicate)),
+            };
+            if let Some((train_index, kind)) = found {
+                overlaps.push(SplitOverlap {
+                    eval_index: eval_index as u32,
+                    train_index: train_index as u32,
+                    field: field.to_string(),
+                    kind,
+                });
+            }
+        }
+    }
+
+    let (overlaps, output_overlaps): (Vec<_>, Vec<_>) =
+        overlaps.into_iter().partition(|o| o.field == "input");
+    let leaked: HashSet<u32> = overlaps.iter().map(|o| o.eval_index).collect();
+    let output_overlaps = output_overlaps
+        .into_iter()
+        .filter(|o| !leaked.contains(&o.eval_index))
+        .collect();
+    let mut leaked_eval_indices: Vec<u32> = leaked.iter().copied().collect();
+    leaked_eval_indices.sort_unstable();
+
+    let cleaned_eval: Option<Vec<TrainingExample>> = remove_leaked.unwrap_or(false).then(|| {
+        eval.iter()
+            .enumerate()
+            .filter(|(i, _)| !leaked.contains(&(*i as u32)))
+            .map(|(_, e)| e.clone())
+            .collect()
+    });
+
+    let cleaned_version = match (&cleaned_eval, &eval_dataset_id) {
+        (Some(cleaned), Some(id)) if !leaked.is_empty() => {
+            Some(record_dataset_edit(state, id, cleaned.clone(), "remove_leaked").await?)
+        }
+        _ => None,
+    };
+
+    Ok(LeakageReport {
+        leakage_rate: leaked_eval_indices.len() as f32 / eval.len() as f32,
+        overlaps,
+        output_overlaps,
+        leaked_eval_indices,
+        cleaned_eval,
+        cleaned_version,
+    })
+}
+
+/// A text prepared for duplicate comparison
+struct LeakText {
+    raw: String,
+    normalized: String,
+    words: HashSet<String>,
+}
+
+impl LeakText {
+    fn new(raw: String) -> Self {
+        let normalized = normalize_text(&raw);
+        let words = normalized.split(' ').map(str::to_string).collect();
+        Self { raw, normalized, words }
+    }
+
+    /// Jaccard similarity of the word sets
+    fn similarity(&self, other: &LeakText) -> f64 {
+        let (small, large) = if self.words.len() <= other.words.len() {
+            (&self.words, &other.words)
+        } else {
+            (&other.words, &self.words)
+        };
+        // Sets this different in size can't reach the threshold
+        let max_possible = small.len() as f64 / large.len().max(1) as f64;
+        if large.is_empty() || max_possible < NEAR_DUPLICATE_SIMILARITY {
+            return 0.0;
+        }
+        let shared = small.intersection(large).count();
+        shared as f64 / (small.len() + large.len() - shared) as f64
+    }
+}
+
+// ============ Field Stats ============
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldStats {
+    /// Examples where the field is present (always all examples for input/output)
+    pub present_count: u32,
+    /// Percent of all examples where the field is present and non-blank
+    pub non_empty_percent: f32,
+    pub avg_chars: u32,
+    pub max_chars: u32,
+    pub min_chars: u32,
+    pub avg_tokens: u32,
+    pub max_tokens: u32,
+    pub min_tokens: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatasetFieldStats {
+    pub num_samples: u32,
+    pub input: FieldStats,
+    pub output: FieldStats,
+    pub system: FieldStats,
+}
+
+/// Get length statistics broken down by input, output, and system prompt
+///
+/// Pass either `examples` or the `dataset_id` of a registered dataset, whose
+/// cached token counts are used.
+#[tauri::command]
+pub async fn field_stats(
+    state: State<'_, AppState>,
+    examples: Option<Vec<TrainingExample>>,
+    dataset_id: Option<String>,
+) -> Result<DatasetFieldStats, String> {
+    let result = field_stats_inner(&state, examples, dataset_id).await;
+    state.capture("field_stats", result).await
+}
+
+async fn field_stats_inner(
+    state: &AppState,
+    examples: Option<Vec<TrainingExample>>,
+    dataset_id: Option<String>,
+) -> Result<DatasetFieldStats, String> {
+    let (examples, tokens) = dataset_with_tokens(state, examples, dataset_id).await?;
+    if examples.is_empty() {
+        return Err("Dataset is empty".to_string());
+    }
+
+    let total = examples.len();
+    let rows = || examples.iter().zip(&tokens);
+
+    Ok(DatasetFieldStats {
+        num_samples: total as u32,
+        input: compute_field_stats(
+            rows().map(|(e, t)| Some((e.input.as_str(), t.input))),
+            total,
+        ),
+        output: compute_field_stats(
+            rows().map(|(e, t)| Some((e.output.as_str(), t.output))),
+            total,
+        ),
+        system: compute_field_stats(
+            rows().map(|(e, t)| e.system.as_deref().map(|s| (s, t.system))),
+            total,
+        ),
+    })
+}
+
+/// Stats over a field's values, each paired with its token count
+fn compute_field_stats<'a>(
+    values: impl Iterator<Item = Option<(&'a str, u32)>>,
+    total: usize,
+) -> FieldStats {
+    let (present, tokens): (Vec<&str>, Vec<u32>) = values.flatten().unzip();
+    let non_empty = present.iter().filter(|v| !v.trim().is_empty()).count();
+    let chars: Vec<u32> = present.iter().map(|v| v.chars().count() as u32).collect();
+    let count = present.len().max(1) as u32;
+
+    FieldStats {
+        present_count: present.len() as u32,
+        non_empty_percent: non_empty as f32 / total as f32 * 100.0,
+        avg_chars: chars.iter().sum::<u32>() / count,
+        max_chars: chars.iter().copied().max().unwrap_or(0),
+        min_chars: chars.iter().copied().min().unwrap_or(0),
+        avg_tokens: tokens.iter().sum::<u32>() / count,
+        max_tokens: tokens.iter().copied().max().unwrap_or(0),
+        min_tokens: tokens.iter().copied().min().unwrap_or(0),
+    }
+}
+
+// ============ Sequence Length ============
+
+const SEQ_LENGTH_PERCENTILE: f64 = 95.0;
+const MIN_SEQ_LENGTH: u32 = 128;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeqLengthRecommendation {
+    /// Recommended max sequence length (power of two, capped at context size)
+    pub recommended_length: u32,
+    /// Token length covering 95% of examples
+    pub p95_tokens: u32,
+    /// Longest example in tokens
+    pub max_tokens: u32,
+    /// Context window of the chosen model
+    pub model_context_length: u32,
+    /// Examples longer than the recommended length
+    pub truncated_count: u32,
+    pub truncated_percent: f32,
+}
+
+/// Recommend a training sequence length from the dataset's length distribution
+#[tauri::command]
+pub async fn recommend_seq_length(
+    state: State<'_, AppState>,
+    examples: Option<Vec<TrainingExample>>,
+    dataset_id: Option<String>,
+    model_id: String,
+) -> Result<SeqLengthRecommendation, String> {
+    let result = recommend_seq_length_inner(&state, examples, dataset_id, model_id).await;
+    state.capture("recommend_seq_length", result).await
+}
+
+async fn recommend_seq_length_inner(
+    state: &AppState,
+    examples: Option<Vec<TrainingExample>>,
+    dataset_id: Option<String>,
+    model_id: String,
+) -> Result<SeqLengthRecommendation, String> {
+    let (_, tokens) = dataset_with_tokens(state, examples, dataset_id).await?;
+    if tokens.is_empty() {
+        return Err("Dataset is empty".to_string());
+    }
+
+    let mut lengths: Vec<u32> = tokens.iter().map(ExampleTokens::total).collect();
+    lengths.sort_unstable();
+
+    let p95_tokens = percentile(&lengths, SEQ_LENGTH_PERCENTILE);
+    let max_tokens = *lengths.last().unwrap_or(&0);
+    let model_context_length = model_context_length(&model_id);
+
+    let recommended_length = p95_tokens
+        .max(MIN_SEQ_LENGTH)
+        .checked_next_power_of_two()
+        .unwrap_or(u32::MAX)
+        .min(model_context_length);
+
+    let truncated_count = lengths.iter().filter(|&&l| l > recommended_length).count() as u32;
+
+    Ok(SeqLengthRecommendation {
+        recommended_length,
+        p95_tokens,
+        max_tokens,
+        model_context_length,
+        truncated_count,
+        truncated_percent: truncated_count as f32 / lengths.len() as f32 * 100.0,
+    })
+}
+
+/// Context window for known base model families (conservative default otherwise)
+pub fn model_context_length(model_id: &str) -> u32 {
+    let id = model_id.to_lowercase();
+    if id.contains("llama-3.1") || id.contains("llama-3.2") || id.contains("llama-3.3") {
+        131072
+    } else if id.contains("llama-3") {
+        8192
+    } else if id.contains("qwen") || id.contains("mistral") {
+        32768
+    } else {
+        4096
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_semicolon_csv() {
+        let content = "input;output;system\nHi;Hello, there;Be kind\n\"a;b\";c;\n";
+        let examples = parse_csv(content, &CsvOptions::default()).unwrap();
+
+        assert_eq!(examples.len(), 2);
+        assert_eq!(examples[0].output, "Hello, there");
+        assert_eq!(examples[0].system.as_deref(), Some("Be kind"));
+        assert_eq!(examples[1].input, "a;b");
+    }
+
+    #[test]
+    fn parses_tab_csv_with_joined_output_columns() {
+        let content = "prompt\tanswer\tnotes\nQ1\tA1\tN1\nQ2\tA2\t\n";
+        let options = CsvOptions {
+            output_columns: Some(vec!["answer".to_string(), "notes".to_string()]),
+            ..Default::default()
+        };
+        let examples = parse_csv(content, &options).unwrap();
+
+        assert_eq!(examples.len(), 2);
+        assert_eq!(examples[0].output, "A1\nN1");
+        assert_eq!(examples[1].output, "A2");
+    }
+
+    #[test]
+    fn parses_headerless_csv_by_position() {
+        let content = "x\tQ1\tA1\nx\tQ2\tA2\n";
+        let options = CsvOptions {
+            delimiter: Some('\t'),
+            no_header: true,
+            input_column: Some("1".to_string()),
+            output_columns: Some(vec!["2".to_string()]),
+            ..Default::default()
+        };
+        let examples = parse_csv(content, &options).unwrap();
+
+        assert_eq!(examples.len(), 2);
+        assert_eq!(examples[0].input, "Q1");
+        assert_eq!(examples[1].output, "A2");
+    }
+
+    #[test]
+    fn maps_headers_with_inconsistent_casing_and_synonyms() {
+        let content = "\u{feff} Question ,ANSWER,System_Prompt\nQ1,A1,S1\n";
+        let ParsedCsv { examples, mapping, .. } =
+            parse_csv_mapped(content, &CsvOptions::default()).unwrap();
+
+        assert_eq!(examples.len(), 1);
+        assert_eq!(examples[0].input, "Q1");
+        assert_eq!(examples[0].system.as_deref(), Some("S1"));
+        assert_eq!(mapping.input, "Question");
+        assert_eq!(mapping.output, vec!["ANSWER".to_string()]);
+        assert_eq!(mapping.system.as_deref(), Some("System_Prompt"));
+    }
+
+    #[test]
+    fn csv_round_trips_embedded_newlines_and_quotes() {
+        let examples = vec![
+            TrainingExample {
+                input: "Say \"hi\"\ntwice".to_string(),
+                output: "hi\r\nhi".to_string(),
+                system: None,
+                metadata: None,
+                weight: None,
+            },
+            TrainingExample {
+                input: "plain".to_string(),
+                output: "\"quoted\", then\n\nblank line".to_string(),
+                system: None,
+                metadata: None,
+                weight: None,
+            },
+        ];
+        let csv = serialize_examples(&examples, "csv").unwrap();
+        let parsed = parse_csv(&csv, &CsvOptions::default()).unwrap();
+
+        assert_eq!(parsed.len(), 2);
+        assert!(first_divergence(&examples, &parsed).is_none());
+    }
+
+    #[test]
+    fn csv_keeps_empty_system_and_metadata_and_reports_short_rows() {
+        let examples = vec![
+            TrainingExample {
+                input: "a".to_string(),
+                output: "b".to_string(),
+                system: Some(String::new()),
+                metadata: Some(HashMap::from([("domain".to_string(), "law".to_string())])),
+                weight: None,
+            },
+            TrainingExample {
+                input: "c".to_string(),
+                output: "d".to_string(),
+                system: None,
+                metadata: None,
+                weight: None,
+            },
+        ];
+        let csv = serialize_examples(&examples, "csv").unwrap();
+        assert!(first_divergence(&examples, &parse_csv(&csv, &CsvOptions::default()).unwrap())
+            .is_none());
+
+        let parsed = parse_csv_mapped("input,output\nok,fine\nshort\n", &CsvOptions::default())
+            .unwrap();
+        assert_eq!(parsed.examples.len(), 1);
+        assert_eq!(parsed.skipped_rows.len(), 1);
+        assert_eq!(parsed.skipped_rows[0].line, 3);
+    }
+
+    #[test]
+    fn reads_decimal_comma_weights_and_rejects_unreadable_ones() {
+        let content = "input;output;weight\na;b;0,5\nc;d;2\n";
+        let examples = parse_csv(content, &CsvOptions::default()).unwrap();
+        assert_eq!(examples[0].weight, Some(0.5));
+        assert_eq!(examples[1].weight, Some(2.0));
+
+        let error = parse_csv("input,output,weight\na,b,heavy\n", &CsvOptions::default())
+            .unwrap_err();
+        assert!(error.starts_with("Line 2:"));
+    }
+}
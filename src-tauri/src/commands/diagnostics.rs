@@ -0,0 +1,255 @@
+//! Diagnostics commands for benchmarking and troubleshooting
+
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use tauri::{AppHandle, Manager, State};
+use crate::api::anthropic::{AgentSettings, AgentType};
+use crate::api::yutori::PollConfig;
+use crate::commands::settings::{self, ConnectionCheck};
+use crate::state::{redact_secrets, AppState, ErrorRecord};
+use serde::{Deserialize, Serialize};
+
+// ============ Error Log ============
+
+/// Default number of entries returned by `recent_errors`
+const DEFAULT_RECENT_ERRORS: usize = 20;
+
+/// Most recent command failures (newest first), with secrets redacted
+#[tauri::command]
+pub async fn recent_errors(
+    state: State<'_, AppState>,
+    limit: Option<usize>,
+) -> Result<Vec<ErrorRecord>, String> {
+    Ok(state.recent_errors(limit.unwrap_or(DEFAULT_RECENT_ERRORS)).await)
+}
+
+// ============ Diagnostics Bundle ============
+
+/// Errors included in a diagnostics bundle
+const BUNDLE_RECENT_ERRORS: usize = 20;
+
+const ALL_AGENTS: [AgentType; 4] = [
+    AgentType::Intent,
+    AgentType::Validation,
+    AgentType::Config,
+    AgentType::General,
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VersionInfo {
+    pub app: String,
+    pub crate_version: String,
+    pub tauri: String,
+    pub os: String,
+    pub arch: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentConfigSummary {
+    pub agent: AgentType,
+    /// Settings in effect (stored overrides over built-in defaults)
+    pub settings: AgentSettings,
+    /// A custom system prompt is set (its text is not included)
+    pub prompt_overridden: bool,
+}
+
+/// Effective configuration with secrets reduced to whether they are set
+#[derive(Debug, Clone, Serialize)]
+pub struct EffectiveConfig {
+    pub configured_keys: BTreeMap<String, bool>,
+    pub agents: Vec<AgentConfigSummary>,
+    pub research_polling: PollConfig,
+    pub transcript_logging: bool,
+    pub registered_datasets: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticsBundle {
+    pub generated_at: String,
+    pub versions: VersionInfo,
+    pub config: EffectiveConfig,
+    /// Latest `check_all_connections` results, if it has been run
+    pub connection_checks: Vec<ConnectionCheck>,
+    /// Newest first, with secrets redacted
+    pub recent_errors: Vec<ErrorRecord>,
+}
+
+/// Collect versions, redacted config, connection results and recent errors
+/// for pasting into a bug report. Never includes API keys, prompt text or
+/// dataset content.
+#[tauri::command]
+pub async fn diagnostics_bundle(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<DiagnosticsBundle, String> {
+    let result = diagnostics_bundle_inner(app, &state).await;
+    state.capture("diagnostics_bundle", result).await
+}
+
+async fn diagnostics_bundle_inner(
+    app: AppHandle,
+    state: &AppState,
+) -> Result<DiagnosticsBundle, String> {
+    let package = app.package_info();
+    let versions = VersionInfo {
+        app: package.version.to_string(),
+        crate_version: env!("CARGO_PKG_VERSION").to_string(),
+        tauri: tauri::VERSION.to_string(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+    };
+
+    let mut configured_keys = BTreeMap::new();
+    for service in settings::ALL_SERVICES {
+        let configured = settings::has_api_key(state, service).await?;
+        configured_keys.insert(service.to_string(), configured);
+    }
+
+    let agents = {
+        let client = state.anthropic.lock().await;
+        ALL_AGENTS
+            .iter()
+            .map(|agent| AgentConfigSummary {
+                agent: *agent,
+                settings: client.agent_settings(*agent),
+                prompt_overridden: client.has_prompt_override(*agent),
+            })
+            .collect()
+    };
+
+    let config = EffectiveConfig {
+        configured_keys,
+        agents,
+        research_polling: state.yutori.lock().await.poll_config(),
+        transcript_logging: state.transcripts.lock().await.enabled,
+        registered_datasets: state.datasets.lock().await.len(),
+    };
+
+    // Connection failures can echo request details, so redact them like errors
+    let connection_checks = state
+        .connection_checks
+        .lock()
+        .await
+        .values()
+        .cloned()
+        .map(|mut check| {
+            check.reason = check.reason.map(|r| redact_secrets(&r));
+            check
+        })
+        .collect();
+
+    Ok(DiagnosticsBundle {
+        generated_at: chrono::Utc::now().to_rfc3339(),
+        versions,
+        config,
+        connection_checks,
+        recent_errors: state.recent_errors(BUNDLE_RECENT_ERRORS).await,
+    })
+}
+
+// ============ Latency Probe ============
+
+const PROBE_TRANSCRIPT: &str = "Generate 100 examples for a customer support assistant";
+const PROBE_CHAT_MESSAGE: &str = "Reply with one short sentence.";
+const PROBE_TTS_TEXT: &str = "Your training run has started.";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencyStage {
+    pub stage: String,
+    pub duration_ms: u64,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencyReport {
+    pub stages: Vec<LatencyStage>,
+    pub total_ms: u64,
+}
+
+/// Time each stage of a canned voice turn (transcribe, intent, chat, TTS)
+#[tauri::command]
+pub async fn latency_probe(state: State<'_, AppState>) -> Result<LatencyReport, String> {
+    let mut stages = Vec::new();
+
+    // Stages call the clients directly so the probe doesn't land in the error
+    // log, the transcript library or the agent usage totals
+    let start = Instant::now();
+    let result = state.elevenlabs.lock().await.transcribe(&probe_audio_clip()).await;
+    stages.push(stage_result("transcribe", start, result));
+
+    let start = Instant::now();
+    let result = state
+        .anthropic
+        .lock()
+        .await
+        .chat_with_agent(AgentType::Intent, PROBE_TRANSCRIPT)
+        .await;
+    stages.push(stage_result("parse_intent", start, result));
+
+    let start = Instant::now();
+    let result = state
+        .anthropic
+        .lock()
+        .await
+        .chat_with_agent(AgentType::General, PROBE_CHAT_MESSAGE)
+        .await;
+    stages.push(stage_result("chat", start, result));
+
+    let start = Instant::now();
+    let result = state
+        .elevenlabs
+        .lock()
+        .await
+        .text_to_speech(PROBE_TTS_TEXT, None, None, None)
+        .await;
+    stages.push(stage_result("text_to_speech", start, result));
+
+    let total_ms = stages.iter().map(|s| s.duration_ms).sum();
+
+    Ok(LatencyReport { stages, total_ms })
+}
+
+fn stage_result<T, E: std::fmt::Display>(
+    stage: &str,
+    start: Instant,
+    result: Result<T, E>,
+) -> LatencyStage {
+    LatencyStage {
+        stage: stage.to_string(),
+        duration_ms: start.elapsed().as_millis() as u64,
+        success: result.is_ok(),
+        error: result.err().map(|e| e.to_string()),
+    }
+}
+
+/// Half a second of a 440Hz tone as a 16kHz mono 16-bit WAV, base64-encoded
+fn probe_audio_clip() -> String {
+    const SAMPLE_RATE: u32 = 16_000;
+    const NUM_SAMPLES: u32 = SAMPLE_RATE / 2;
+
+    let data_len = NUM_SAMPLES * 2;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes()); // fmt chunk size
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes()); // byte rate
+    wav.extend_from_slice(&2u16.to_le_bytes()); // block align
+    wav.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+
+    for i in 0..NUM_SAMPLES {
+        let t = i as f32 / SAMPLE_RATE as f32;
+        let sample = ((t * 440.0 * std::f32::consts::TAU).sin() * 8000.0) as i16;
+        wav.extend_from_slice(&sample.to_le_bytes());
+    }
+
+    BASE64.encode(wav)
+}
@@ -1,5 +1,6 @@
 pub mod agents;
 pub mod data;
+pub mod diagnostics;
 pub mod research;
 pub mod settings;
 pub mod training;
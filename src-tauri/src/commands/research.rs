@@ -2,10 +2,18 @@
 //!
 //! SESSION 2: Implement these commands
 
-use tauri::State;
-use crate::state::AppState;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_store::StoreExt;
+use tokio::sync::Mutex;
+use crate::state::{AppState, STORE_FILE};
+use crate::api::yutori::{self, PollConfig, ResearchResult, YutoriClient, YutoriError};
 use serde::{Deserialize, Serialize};
 
+/// Store key prefix for research tasks that have been started but not finished
+const PENDING_RESEARCH_PREFIX: &str = "pending_research:";
+/// Launches on which a task may time out or hit network errors before it's given up on
+const MAX_RESUME_ATTEMPTS: u32 = 3;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResearchRequest {
     pub task_description: String,
@@ -47,32 +55,181 @@ pub struct ResearchStatus {
     pub result: Option<ResearchResponse>,
 }
 
+/// A research task persisted so polling can resume after an app restart
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingResearch {
+    pub research_id: String,
+    pub request: ResearchRequest,
+    pub started_at: String,
+    /// Resumptions that ended without a result (timeout or network error)
+    #[serde(default)]
+    pub failed_resumes: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResearchFailedEvent {
+    pub research_id: String,
+    pub error: String,
+}
+
 /// Research domain and best practices for a training task
 #[tauri::command]
 pub async fn research_domain(
+    app: AppHandle,
     state: State<'_, AppState>,
     request: ResearchRequest,
 ) -> Result<ResearchResponse, String> {
-    let client = state.yutori.lock().await;
-
-    let result = client
-        .research_ml_task(
-            &request.task_description,
-            request.model_type.as_deref().unwrap_or("llama"),
-            request.training_type.as_deref().unwrap_or("sft"),
-        )
-        .await
-        .map_err(|e| e.to_string())?;
-
-    Ok(ResearchResponse {
-        research_id: uuid::Uuid::new_v4().to_string(),
+    let result = research_domain_inner(app, &state, request).await;
+    state.capture("research_domain", result).await
+}
+
+async fn research_domain_inner(
+    app: AppHandle,
+    state: &AppState,
+    request: ResearchRequest,
+) -> Result<ResearchResponse, String> {
+    let yutori_request = YutoriClient::ml_research_request(
+        &request.task_description,
+        request.model_type.as_deref().unwrap_or("llama"),
+        request.training_type.as_deref().unwrap_or("sft"),
+    );
+
+    let research_id = {
+        let client = state.yutori.lock().await;
+        client
+            .start_research(yutori_request)
+            .await
+            .map_err(|e| e.to_string())?
+    };
+
+    let pending = PendingResearch {
+        research_id: research_id.clone(),
+        request: request.clone(),
+        started_at: chrono::Utc::now().to_rfc3339(),
+        failed_resumes: 0,
+    };
+    track_pending_research(&app, &pending)?;
+
+    let result = poll_research(&state.yutori, &research_id).await;
+    if !is_resumable(&result, 0) {
+        untrack_pending_research(&app, &research_id);
+    }
+
+    let result = result.map_err(|e| e.to_string())?;
+    Ok(build_research_response(research_id, &request, &result))
+}
+
+/// List research tasks that were started but have not reached a terminal state
+#[tauri::command]
+pub async fn list_pending_research(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Vec<PendingResearch>, String> {
+    let result = list_pending_research_inner(app).await;
+    state.capture("list_pending_research", result).await
+}
+
+async fn list_pending_research_inner(app: AppHandle) -> Result<Vec<PendingResearch>, String> {
+    load_pending_research(&app)
+}
+
+/// Resume polling research persisted by a previous session, emitting
+/// `research-completed` / `research-failed` as each one finishes
+///
+/// Tasks that time out or hit a network error stay pending for the next launch
+/// without an event, up to `MAX_RESUME_ATTEMPTS` launches in total.
+pub async fn resume_pending_research(app: AppHandle) {
+    let pending = match load_pending_research(&app) {
+        Ok(pending) => pending,
+        Err(e) => {
+            tracing::warn!("Failed to load pending research: {}", e);
+            return;
+        }
+    };
+
+    if pending.is_empty() {
+        return;
+    }
+
+    tracing::info!("Resuming {} pending research task(s)", pending.len());
+
+    let state = app.state::<AppState>();
+    let tasks = pending.into_iter().map(|p| {
+        let app = app.clone();
+        let state = state.clone();
+        async move {
+            let result = poll_research(&state.yutori, &p.research_id).await;
+            if is_resumable(&result, p.failed_resumes) {
+                let retry = PendingResearch {
+                    failed_resumes: p.failed_resumes + 1,
+                    ..p.clone()
+                };
+                if let Err(e) = track_pending_research(&app, &retry) {
+                    tracing::warn!("Failed to update pending research {}: {}", p.research_id, e);
+                }
+                tracing::info!("Research {} still pending", p.research_id);
+                return;
+            }
+            untrack_pending_research(&app, &p.research_id);
+
+            let emitted = match result {
+                Ok(result) => app.emit(
+                    "research-completed",
+                    build_research_response(p.research_id.clone(), &p.request, &result),
+                ),
+                Err(e) => app.emit(
+                    "research-failed",
+                    ResearchFailedEvent {
+                        research_id: p.research_id.clone(),
+                        error: e.to_string(),
+                    },
+                ),
+            };
+            if let Err(e) = emitted {
+                tracing::warn!("Failed to emit research event for {}: {}", p.research_id, e);
+            }
+        }
+    });
+
+    futures::future::join_all(tasks).await;
+}
+
+/// Poll until the research completes without holding the client's lock, so
+/// long-running research doesn't block other Yutori calls
+async fn poll_research(
+    yutori: &Mutex<YutoriClient>,
+    research_id: &str,
+) -> Result<ResearchResult, YutoriError> {
+    let poller = yutori.lock().await.poller()?;
+    poller.wait(research_id).await
+}
+
+/// Whether a research task may still finish server-side and should be kept for
+/// resumption, given how many earlier resumptions ended without a result
+fn is_resumable(result: &Result<ResearchResult, YutoriError>, failed_resumes: u32) -> bool {
+    let may_finish = matches!(
+        result,
+        Err(YutoriError::TimedOut { .. } | YutoriError::RequestFailed(_))
+    );
+    may_finish && failed_resumes + 1 < MAX_RESUME_ATTEMPTS
+}
+
+fn build_research_response(
+    research_id: String,
+    request: &ResearchRequest,
+    result: &ResearchResult,
+) -> ResearchResponse {
+    let ml_result = yutori::summarize_ml_research(result);
+
+    ResearchResponse {
+        research_id,
         summary: format!(
             "Research completed for {} task in {} domain",
             request.task_description, request.domain
         ),
-        best_practices: result.best_practices,
-        data_patterns: result.data_patterns,
-        recommended_params: result
+        best_practices: ml_result.best_practices,
+        data_patterns: ml_result.data_patterns,
+        recommended_params: ml_result
             .recommended_params
             .into_iter()
             .map(|p| ParamRecommendation {
@@ -81,9 +238,71 @@ pub async fn research_domain(
                 rationale: p.rationale,
             })
             .collect(),
-        pitfalls: result.pitfalls,
+        pitfalls: ml_result.pitfalls,
         sources: vec![], // Yutori will populate this
-    })
+    }
+}
+
+fn track_pending_research(app: &AppHandle, pending: &PendingResearch) -> Result<(), String> {
+    let store = app.store(STORE_FILE).map_err(|e| e.to_string())?;
+    let value = serde_json::to_value(pending).map_err(|e| e.to_string())?;
+    store.set(format!("{}{}", PENDING_RESEARCH_PREFIX, pending.research_id), value);
+    store.save().map_err(|e| e.to_string())
+}
+
+fn untrack_pending_research(app: &AppHandle, research_id: &str) {
+    let Ok(store) = app.store(STORE_FILE) else {
+        return;
+    };
+    store.delete(format!("{}{}", PENDING_RESEARCH_PREFIX, research_id));
+    if let Err(e) = store.save() {
+        tracing::warn!("Failed to persist research store: {}", e);
+    }
+}
+
+fn load_pending_research(app: &AppHandle) -> Result<Vec<PendingResearch>, String> {
+    let store = app.store(STORE_FILE).map_err(|e| e.to_string())?;
+
+    let mut pending: Vec<PendingResearch> = store
+        .keys()
+        .into_iter()
+        .filter(|key| key.starts_with(PENDING_RESEARCH_PREFIX))
+        .filter_map(|key| store.get(&key))
+        .filter_map(|value| serde_json::from_value(value).ok())
+        .collect();
+    pending.sort_by(|a, b| a.started_at.cmp(&b.started_at));
+
+    Ok(pending)
+}
+
+/// Change how research tasks are polled; returns the previous settings
+#[tauri::command]
+pub async fn set_research_polling(
+    state: State<'_, AppState>,
+    config: PollConfig,
+) -> Result<PollConfig, String> {
+    let result = set_research_polling_inner(&state, config).await;
+    state.capture("set_research_polling", result).await
+}
+
+async fn set_research_polling_inner(
+    state: &AppState,
+    config: PollConfig,
+) -> Result<PollConfig, String> {
+    if config.initial_delay_ms == 0 || config.max_attempts == 0 {
+        return Err("Initial delay and max attempts must be greater than 0".to_string());
+    }
+    if config.max_delay_ms < config.initial_delay_ms {
+        return Err("Max delay can't be shorter than the initial delay".to_string());
+    }
+    if config.max_concurrent_polls == 0 {
+        return Err("At least one concurrent poll is required".to_string());
+    }
+
+    let mut client = state.yutori.lock().await;
+    let previous = client.poll_config();
+    client.set_poll_config(config);
+    Ok(previous)
 }
 
 /// Get status of an ongoing research task
@@ -92,6 +311,11 @@ pub async fn get_research_status(
     state: State<'_, AppState>,
     research_id: String,
 ) -> Result<ResearchStatus, String> {
+    let result = get_research_status_inner(research_id).await;
+    state.capture("get_research_status", result).await
+}
+
+async fn get_research_status_inner(research_id: String) -> Result<ResearchStatus, String> {
     // For synchronous research, just return completed
     // In a real implementation, this would check async research status
 
@@ -2,7 +2,17 @@
 //!
 //! SESSION 2: Implement these commands
 
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
 use tauri::State;
+use crate::api::{
+    anthropic::AnthropicClient,
+    elevenlabs::ElevenLabsClient,
+    tinker::{TinkerClient, TinkerError},
+    tonic::TonicClient,
+    yutori::YutoriClient,
+};
 use crate::state::AppState;
 use serde::{Deserialize, Serialize};
 
@@ -40,43 +50,56 @@ pub async fn get_api_keys_status(state: State<'_, AppState>) -> Result<ApiKeysSt
     let tonic = state.tonic.lock().await;
     let yutori = state.yutori.lock().await;
     let tinker = state.tinker.lock().await;
+    let checks = state.connection_checks.lock().await;
+    // Validity from the last `check_all_connections`, if any
+    let status = |service: &str, is_configured: bool| {
+        let check = checks.get(service);
+        ApiKeyStatus {
+            is_configured,
+            is_valid: check.and_then(|c| c.valid),
+            last_checked: check.map(|c| c.checked_at.clone()),
+        }
+    };
 
     Ok(ApiKeysStatus {
-        elevenlabs: ApiKeyStatus {
-            is_configured: elevenlabs.has_api_key(),
-            is_valid: None,
-            last_checked: None,
-        },
-        anthropic: ApiKeyStatus {
-            is_configured: anthropic.has_api_key(),
-            is_valid: None,
-            last_checked: None,
-        },
-        tonic: ApiKeyStatus {
-            is_configured: tonic.has_api_key(),
-            is_valid: None,
-            last_checked: None,
-        },
-        yutori: ApiKeyStatus {
-            is_configured: yutori.has_api_key(),
-            is_valid: None,
-            last_checked: None,
-        },
-        tinker: ApiKeyStatus {
-            is_configured: tinker.has_api_key(),
-            is_valid: None,
-            last_checked: None,
-        },
+        elevenlabs: status("elevenlabs", elevenlabs.has_api_key()),
+        anthropic: status("anthropic", anthropic.has_api_key()),
+        tonic: status("tonic", tonic.has_api_key()),
+        yutori: status("yutori", yutori.has_api_key()),
+        tinker: status("tinker", tinker.has_api_key()),
     })
 }
 
-/// Set an API key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetApiKeyResult {
+    pub service: String,
+    /// The key was stored (always true on success; invalid keys are kept so the UI can warn)
+    pub stored: bool,
+    /// Result of the immediate connection test, `None` when validation was
+    /// skipped or the service couldn't be reached
+    pub valid: Option<bool>,
+    /// Error raised while validating, if any
+    pub error: Option<String>,
+}
+
+/// Set an API key, optionally validating it against the service right away
 #[tauri::command]
 pub async fn set_api_key(
     state: State<'_, AppState>,
     service: String,
     api_key: String,
-) -> Result<bool, String> {
+    validate: Option<bool>,
+) -> Result<SetApiKeyResult, String> {
+    let result = set_api_key_inner(&state, service, api_key, validate).await;
+    state.capture("set_api_key", result).await
+}
+
+async fn set_api_key_inner(
+    state: &AppState,
+    service: String,
+    api_key: String,
+    validate: Option<bool>,
+) -> Result<SetApiKeyResult, String> {
     match service.to_lowercase().as_str() {
         "elevenlabs" => {
             let mut client = state.elevenlabs.lock().await;
@@ -101,41 +124,424 @@ pub async fn set_api_key(
         _ => return Err(format!("Unknown service: {}", service)),
     }
 
-    Ok(true)
+    let mut result = SetApiKeyResult {
+        service: service.to_lowercase(),
+        stored: true,
+        valid: None,
+        error: None,
+    };
+
+    // An unreachable service (offline, timeout, TLS) says nothing about the key
+    if validate.unwrap_or(true) {
+        match check_connection_within(state, &service, DEFAULT_CONNECTION_TIMEOUT).await {
+            Ok(valid) => result.valid = Some(valid),
+            Err(e) => result.error = Some(e),
+        }
+    }
+
+    Ok(result)
+}
+
+/// Whether the service client has a key configured
+pub async fn has_api_key(state: &AppState, service: &str) -> Result<bool, String> {
+    Ok(match service.to_lowercase().as_str() {
+        "elevenlabs" => state.elevenlabs.lock().await.has_api_key(),
+        "anthropic" => state.anthropic.lock().await.has_api_key(),
+        "tonic" => state.tonic.lock().await.has_api_key(),
+        "yutori" => state.yutori.lock().await.has_api_key(),
+        "tinker" => state.tinker.lock().await.has_api_key(),
+        _ => return Err(format!("Unknown service: {}", service)),
+    })
+}
+
+/// Call the service client's `test_connection` with its currently configured key.
+/// `Ok(false)` means the key was rejected; `Err` means the service couldn't be checked
+pub async fn check_connection(state: &AppState, service: &str) -> Result<bool, String> {
+    match service.to_lowercase().as_str() {
+        "elevenlabs" => state
+            .elevenlabs
+            .lock()
+            .await
+            .test_connection()
+            .await
+            .map_err(|e| e.to_string()),
+        "anthropic" => state
+            .anthropic
+            .lock()
+            .await
+            .test_connection()
+            .await
+            .map_err(|e| e.to_string()),
+        "tonic" => state
+            .tonic
+            .lock()
+            .await
+            .test_connection()
+            .await
+            .map_err(|e| e.to_string()),
+        "yutori" => state
+            .yutori
+            .lock()
+            .await
+            .test_connection()
+            .await
+            .map_err(|e| e.to_string()),
+        "tinker" => match state.tinker.lock().await.test_connection().await {
+            Err(TinkerError::Unauthorized) => Ok(false),
+            other => other.map_err(|e| e.to_string()),
+        },
+        _ => Err(format!("Unknown service: {}", service)),
+    }
+}
+
+/// How long a single service's connection test may take before it counts as unreachable
+const DEFAULT_CONNECTION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Services checked by `check_all_connections`
+pub const ALL_SERVICES: &[&str] = &["elevenlabs", "anthropic", "tonic", "yutori", "tinker"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionCheck {
+    pub service: String,
+    pub configured: bool,
+    pub reachable: bool,
+    /// The service accepted the key; `None` when it couldn't be reached
+    pub valid: Option<bool>,
+    /// Why the service is unreachable or invalid ("timeout", "no api key", or the error)
+    pub reason: Option<String>,
+    /// Time spent on the check, capped at the timeout
+    pub elapsed_ms: u64,
+    pub checked_at: String,
+}
+
+/// `check_connection` bounded by `timeout`, failing with "timeout" if the service hangs
+pub async fn check_connection_within(
+    state: &AppState,
+    service: &str,
+    timeout: Duration,
+) -> Result<bool, String> {
+    tokio::time::timeout(timeout, check_connection(state, service))
+        .await
+        .unwrap_or_else(|_| Err("timeout".to_string()))
+}
+
+/// Test every service concurrently, each with its own timeout, so one hung
+/// provider can't stall the others
+#[tauri::command]
+pub async fn check_all_connections(
+    state: State<'_, AppState>,
+    timeout_ms: Option<u64>,
+) -> Result<Vec<ConnectionCheck>, String> {
+    let result = check_all_connections_inner(&state, timeout_ms).await;
+    state.capture("check_all_connections", result).await
+}
+
+async fn check_all_connections_inner(
+    state: &AppState,
+    timeout_ms: Option<u64>,
+) -> Result<Vec<ConnectionCheck>, String> {
+    let timeout = timeout_ms
+        .map(Duration::from_millis)
+        .unwrap_or(DEFAULT_CONNECTION_TIMEOUT);
+
+    let checks = ALL_SERVICES.iter().map(|service| {
+        let state = &state;
+        async move {
+            let started = Instant::now();
+            let configured = has_api_key(state, service).await?;
+            let outcome = if configured {
+                check_connection_within(state, service, timeout).await
+            } else {
+                Err("no api key".to_string())
+            };
+            let elapsed_ms = started.elapsed().min(timeout).as_millis() as u64;
+
+            Ok::<_, String>(ConnectionCheck {
+                service: service.to_string(),
+                configured,
+                reachable: outcome.is_ok(),
+                valid: outcome.as_ref().ok().copied(),
+                reason: match &outcome {
+                    Ok(true) => None,
+                    Ok(false) => Some("key rejected".to_string()),
+                    Err(e) => Some(e.clone()),
+                },
+                elapsed_ms,
+                checked_at: chrono::Utc::now().to_rfc3339(),
+            })
+        }
+    });
+
+    let checks: Vec<ConnectionCheck> =
+        futures::future::join_all(checks).await.into_iter().collect::<Result<_, _>>()?;
+    state.record_connection_checks(&checks).await;
+    Ok(checks)
 }
 
-/// Test an API connection
+/// Test an API connection with the configured key; false when no key is set
+/// or the service rejects it
 #[tauri::command]
 pub async fn test_api_connection(
     state: State<'_, AppState>,
     service: String,
 ) -> Result<bool, String> {
-    // Check if API key is configured
-    let has_key = match service.to_lowercase().as_str() {
-        "elevenlabs" => {
-            let client = state.elevenlabs.lock().await;
-            client.has_api_key()
-        }
-        "anthropic" => {
-            let client = state.anthropic.lock().await;
-            client.has_api_key()
-        }
-        "tonic" => {
-            let client = state.tonic.lock().await;
-            client.has_api_key()
+    let result = test_api_connection_inner(&state, service).await;
+    state.capture("test_api_connection", result).await
+}
+
+async fn test_api_connection_inner(state: &AppState, service: String) -> Result<bool, String> {
+    if !has_api_key(state, &service).await? {
+        return Ok(false);
+    }
+    check_connection_within(state, &service, DEFAULT_CONNECTION_TIMEOUT).await
+}
+
+// ============ Key Rotation ============
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActiveKey {
+    New,
+    Previous,
+    /// The rotation failed and the service had no key before it
+    None,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RotateKeyResult {
+    pub service: String,
+    /// Which key the service is using now
+    pub active: ActiveKey,
+    /// Why the new key was rejected
+    pub error: Option<String>,
+}
+
+/// Replace a service's API key only once the new key passes `test_connection`
+///
+/// The new key is tested on a separate client, so the current key keeps serving
+/// requests until the new one is confirmed. Any failure leaves the previous key
+/// active. Like `set_api_key`, the rotated key lasts until the app restarts;
+/// it isn't written to the store because that file is plaintext.
+#[tauri::command]
+pub async fn rotate_api_key(
+    state: State<'_, AppState>,
+    service: String,
+    new_key: String,
+) -> Result<RotateKeyResult, String> {
+    let result = rotate_api_key_inner(&state, service, new_key).await;
+    state.capture("rotate_api_key", result).await
+}
+
+async fn rotate_api_key_inner(
+    state: &AppState,
+    service: String,
+    new_key: String,
+) -> Result<RotateKeyResult, String> {
+    let service = service.to_lowercase();
+    if !ALL_SERVICES.contains(&service.as_str()) {
+        return Err(format!("Unknown service: {}", service));
+    }
+    let new_key = new_key.trim().to_string();
+    if new_key.is_empty() {
+        return Err("New API key is empty".to_string());
+    }
+    let had_key = has_api_key(state, &service).await?;
+    let previous = if had_key { ActiveKey::Previous } else { ActiveKey::None };
+
+    let rejection = match probe_key(&service, &new_key).await {
+        Ok(true) => None,
+        Ok(false) => Some(format!("{} rejected the new key", service)),
+        Err(e) => Some(format!("Could not validate the new key: {}", e)),
+    };
+    if let Some(e) = rejection {
+        return Ok(RotateKeyResult {
+            service,
+            active: previous,
+            error: Some(e),
+        });
+    }
+
+    install_api_key(state, &service, new_key).await?;
+    Ok(RotateKeyResult {
+        service,
+        active: ActiveKey::New,
+        error: None,
+    })
+}
+
+/// Run `test_connection` on a throwaway client holding `key`
+async fn probe_key(service: &str, key: &str) -> Result<bool, String> {
+    let key = Some(key.to_string());
+    match service {
+        "elevenlabs" => ElevenLabsClient::new(key)
+            .test_connection()
+            .await
+            .map_err(|e| e.to_string()),
+        "anthropic" => AnthropicClient::new(key)
+            .test_connection()
+            .await
+            .map_err(|e| e.to_string()),
+        "tonic" => TonicClient::new(key)
+            .test_connection()
+            .await
+            .map_err(|e| e.to_string()),
+        "yutori" => YutoriClient::new(key)
+            .test_connection()
+            .await
+            .map_err(|e| e.to_string()),
+        "tinker" => TinkerClient::new(key)
+            .test_connection()
+            .await
+            .map_err(|e| e.to_string()),
+        _ => Err(format!("Unknown service: {}", service)),
+    }
+}
+
+async fn install_api_key(state: &AppState, service: &str, key: String) -> Result<(), String> {
+    match service {
+        "elevenlabs" => state.elevenlabs.lock().await.set_api_key(key),
+        "anthropic" => state.anthropic.lock().await.set_api_key(key),
+        "tonic" => state.tonic.lock().await.set_api_key(key),
+        "yutori" => state.yutori.lock().await.set_api_key(key),
+        "tinker" => state.tinker.lock().await.set_api_key(key),
+        _ => return Err(format!("Unknown service: {}", service)),
+    }
+    Ok(())
+}
+
+// ============ Pipeline Readiness ============
+
+/// Pipeline capability and the service backing it
+const PIPELINE_CAPABILITIES: &[(&str, &str)] = &[
+    ("voice", "elevenlabs"),
+    ("agents", "anthropic"),
+    ("research", "yutori"),
+    ("generation", "tonic"),
+    ("training", "tinker"),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Readiness {
+    Ready,
+    MissingKey,
+    InvalidKey,
+    Unreachable,
+    NoModels,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapabilityReadiness {
+    pub service: String,
+    pub status: Readiness,
+    pub detail: Option<String>,
+    /// What the user should do to make this capability ready
+    pub remediation: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineReadiness {
+    /// Every capability is ready
+    pub ready: bool,
+    pub capabilities: BTreeMap<String, CapabilityReadiness>,
+    /// Tinker models that support at least one training type
+    pub available_models: Vec<String>,
+}
+
+/// Check every service the pipeline needs, concurrently, and report per-capability readiness
+#[tauri::command]
+pub async fn pipeline_readiness(state: State<'_, AppState>) -> Result<PipelineReadiness, String> {
+    let checks = PIPELINE_CAPABILITIES.iter().map(|(capability, service)| {
+        let state = &state;
+        async move { (*capability, check_readiness(state, service).await) }
+    });
+    let mut capabilities: BTreeMap<String, CapabilityReadiness> =
+        futures::future::join_all(checks)
+            .await
+            .into_iter()
+            .map(|(capability, readiness)| (capability.to_string(), readiness))
+            .collect();
+
+    let mut available_models = Vec::new();
+    if let Some(training) = capabilities.get_mut("training") {
+        if matches!(training.status, Readiness::Ready) {
+            available_models = check_trainable_models(&state, training).await;
         }
-        "yutori" => {
-            let client = state.yutori.lock().await;
-            client.has_api_key()
+    }
+
+    let ready = capabilities
+        .values()
+        .all(|c| matches!(c.status, Readiness::Ready));
+
+    Ok(PipelineReadiness {
+        ready,
+        capabilities,
+        available_models,
+    })
+}
+
+/// List trainable Tinker models, downgrading `training` if none are available
+async fn check_trainable_models(
+    state: &AppState,
+    training: &mut CapabilityReadiness,
+) -> Vec<String> {
+    let models = match state.tinker.lock().await.get_models().await {
+        Ok(models) => models,
+        Err(e) => {
+            training.status = Readiness::Unreachable;
+            training.detail = Some(format!("Failed to list models: {}", e));
+            training.remediation = Some("Retry shortly; Tinker may be unavailable".to_string());
+            return Vec::new();
         }
-        "tinker" => {
-            let client = state.tinker.lock().await;
-            client.has_api_key()
+    };
+
+    let available: Vec<String> = models
+        .into_iter()
+        .filter(|m| !m.supported_training_types.is_empty())
+        .map(|m| m.id)
+        .collect();
+    if available.is_empty() {
+        training.status = Readiness::NoModels;
+        training.detail = Some("Tinker returned no trainable models".to_string());
+        training.remediation =
+            Some("Check your Tinker account has access to base models".to_string());
+    }
+    available
+}
+
+async fn check_readiness(state: &AppState, service: &str) -> CapabilityReadiness {
+    let readiness = |status, detail: Option<String>, remediation: Option<String>| {
+        CapabilityReadiness {
+            service: service.to_string(),
+            status,
+            detail,
+            remediation,
         }
-        _ => return Err(format!("Unknown service: {}", service)),
     };
 
-    // For hackathon: just return whether the key is configured
-    // TODO: Implement actual connection testing later
-    Ok(has_key)
+    match has_api_key(state, service).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return readiness(
+                Readiness::MissingKey,
+                None,
+                Some(format!("Add a {} API key in Settings", service)),
+            )
+        }
+        Err(e) => return readiness(Readiness::Unreachable, Some(e), None),
+    }
+
+    match check_connection_within(state, service, DEFAULT_CONNECTION_TIMEOUT).await {
+        Ok(true) => readiness(Readiness::Ready, None, None),
+        Ok(false) => readiness(
+            Readiness::InvalidKey,
+            None,
+            Some(format!("Replace the {} API key; the service rejected it", service)),
+        ),
+        Err(e) => readiness(
+            Readiness::Unreachable,
+            Some(e),
+            Some(format!("Check your network connection and the {} key", service)),
+        ),
+    }
 }
@@ -2,13 +2,23 @@
 //!
 //! SESSION 2: Implement these commands
 
-use tauri::State;
-use crate::state::AppState;
+use std::io::Write;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL, Engine};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_store::StoreExt;
+use crate::state::{AppState, STORE_FILE};
+use crate::commands::agents::{self, ConfigChange, ConfigRecommendation, DataStats, TrainingIntent};
+use crate::commands::data::{check_record_weights, read_records, registered_examples};
+use crate::commands::research::ResearchResponse;
+use crate::api::anthropic::{self, AgentType};
+use crate::api::tonic;
 use crate::api::tinker::{
     TrainingConfig, TrainingRun, TrainingType, Hyperparameters, LoraConfig,
-    TrainingStatus, TrainingProgress,
+    TrainingStatus, TrainingProgress, ModelInfo, TinkerClient, LogLine,
 };
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateTrainingRequest {
@@ -90,28 +100,324 @@ impl From<TrainingRun> for TrainingRunResponse {
     }
 }
 
+/// Resolve a user- or LLM-supplied training type (including synonyms) to the enum
+pub fn resolve_training_type(training_type: &str) -> Result<TrainingType, String> {
+    TrainingType::from_alias(training_type)
+        .ok_or_else(|| format!("Unknown training type: {}", training_type))
+}
+
+/// Normalize a training type alias to its canonical name (e.g. "supervised" -> "sft")
+#[tauri::command]
+pub async fn normalize_training_type(
+    state: State<'_, AppState>,
+    training_type: String,
+) -> Result<String, String> {
+    let result = normalize_training_type_inner(training_type).await;
+    state.capture("normalize_training_type", result).await
+}
+
+async fn normalize_training_type_inner(training_type: String) -> Result<String, String> {
+    resolve_training_type(&training_type).map(|t| t.as_str().to_string())
+}
+
+// ============ Tinker Format Validation ============
+
+/// Maximum violations reported individually
+const MAX_FORMAT_VIOLATIONS: usize = 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormatViolation {
+    pub index: u32,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TinkerFormatReport {
+    pub training_type: String,
+    pub is_valid: bool,
+    pub total_records: u32,
+    pub invalid_records: u32,
+    /// The first violations found, in record order
+    pub violations: Vec<FormatViolation>,
+}
+
+impl TinkerFormatReport {
+    /// Convert an invalid report into an error summarizing the first violation
+    pub fn into_result(self) -> Result<Self, String> {
+        if self.is_valid {
+            return Ok(self);
+        }
+
+        match self.violations.first() {
+            Some(first) => Err(format!(
+                "Dataset is not valid for {} training: record {}: {} ({} invalid record(s) total)",
+                self.training_type, first.index, first.message, self.invalid_records
+            )),
+            None => Err("Dataset is empty".to_string()),
+        }
+    }
+}
+
+/// Fields a record must provide for each training type
+fn required_fields(training_type: &TrainingType) -> &'static [&'static [&'static str]] {
+    match training_type {
+        TrainingType::Sft => &[&["input", "prompt"], &["output", "completion", "response"]],
+        TrainingType::Dpo => &[&["input", "prompt"], &["chosen"], &["rejected"]],
+        TrainingType::Rl | TrainingType::Grpo | TrainingType::Ppo | TrainingType::Gkd => {
+            &[&["input", "prompt"]]
+        }
+    }
+}
+
+/// Check records against the JSONL shape Tinker expects for a training type
+pub fn check_tinker_format(records: &[Value], training_type: &TrainingType) -> TinkerFormatReport {
+    let mut violations = Vec::new();
+    let mut invalid_records = 0;
+
+    for (index, record) in records.iter().enumerate() {
+        let problem = match record.as_object() {
+            None => Some("record is not a JSON object".to_string()),
+            Some(obj) => required_fields(training_type).iter().find_map(|alternatives| {
+                let present = alternatives.iter().any(|field| {
+                    obj.get(*field)
+                        .and_then(Value::as_str)
+                        .map(|v| !v.trim().is_empty())
+                        .unwrap_or(false)
+                });
+                (!present).then(|| format!("missing non-empty '{}'", alternatives.join("' or '")))
+            })
+            .or_else(|| match obj.get("weight") {
+                None => None,
+                Some(weight) => match weight.as_f64() {
+                    Some(w) if w.is_finite() && w >= 0.0 => None,
+                    _ => Some(format!("invalid weight {}; must be a non-negative number", weight)),
+                },
+            }),
+        };
+
+        if let Some(message) = problem {
+            invalid_records += 1;
+            if violations.len() < MAX_FORMAT_VIOLATIONS {
+                violations.push(FormatViolation {
+                    index: index as u32,
+                    message,
+                });
+            }
+        }
+    }
+
+    TinkerFormatReport {
+        training_type: training_type.as_str().to_string(),
+        is_valid: invalid_records == 0 && !records.is_empty(),
+        total_records: records.len() as u32,
+        invalid_records,
+        violations,
+    }
+}
+
+/// Verify examples (or a dataset file) match what a training type needs
+#[tauri::command]
+pub async fn validate_tinker_format(
+    state: State<'_, AppState>,
+    examples: Option<Vec<Value>>,
+    file_path: Option<String>,
+    training_type: String,
+) -> Result<TinkerFormatReport, String> {
+    let result = validate_tinker_format_inner(examples, file_path, training_type).await;
+    state.capture("validate_tinker_format", result).await
+}
+
+async fn validate_tinker_format_inner(
+    examples: Option<Vec<Value>>,
+    file_path: Option<String>,
+    training_type: String,
+) -> Result<TinkerFormatReport, String> {
+    let training_type = resolve_training_type(&training_type)?;
+
+    let records = match (examples, file_path) {
+        (Some(examples), _) => examples,
+        (None, Some(path)) => read_records(&path, None)?,
+        (None, None) => return Err("Either examples or file_path is required".to_string()),
+    };
+
+    Ok(check_tinker_format(&records, &training_type))
+}
+
 /// Create a new training run
 #[tauri::command]
 pub async fn create_training_run(
     state: State<'_, AppState>,
     request: CreateTrainingRequest,
+) -> Result<TrainingRunResponse, String> {
+    let result = start_training_run(&state, request).await;
+    state.capture("create_training_run", result).await
+}
+
+/// Interval between progress polls for a watched run
+const RUN_WATCH_INTERVAL_MS: u64 = 5000;
+/// Consecutive failed polls after which a run watcher gives up
+const MAX_RUN_WATCH_ERRORS: u32 = 5;
+/// How long a run watcher keeps polling before giving up (e.g. on a run stuck in pending)
+const MAX_RUN_WATCH_SECS: u64 = 12 * 60 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchedTrainingRun {
+    pub run: TrainingRunResponse,
+    /// Pass to `cancel_run_watch` to stop the progress events
+    pub watch_id: String,
+}
+
+/// Create a run and, only if that succeeds, start a background watcher that
+/// emits `training-progress` events (starting with the new run) until it
+/// finishes, is cancelled with `cancel_run_watch`, or times out
+#[tauri::command]
+pub async fn create_and_watch_training_run(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    request: CreateTrainingRequest,
+) -> Result<WatchedTrainingRun, String> {
+    let result = match start_training_run(&state, request).await {
+        Ok(run) => {
+            let watch_id = format!("run-watch-{}", run.id);
+            let cancel = state.register_cancellation(&watch_id).await;
+            tauri::async_runtime::spawn(watch_run_progress(
+                app,
+                run.clone(),
+                watch_id.clone(),
+                cancel,
+            ));
+            Ok(WatchedTrainingRun { run, watch_id })
+        }
+        Err(e) => Err(e),
+    };
+    state.capture("create_and_watch_training_run", result).await
+}
+
+/// Stop the progress watcher started by `create_and_watch_training_run`
+#[tauri::command]
+pub async fn cancel_run_watch(
+    state: State<'_, AppState>,
+    watch_id: String,
+) -> Result<bool, String> {
+    Ok(state.cancel(&watch_id).await)
+}
+
+async fn watch_run_progress(
+    app: AppHandle,
+    run: TrainingRunResponse,
+    watch_id: String,
+    cancel: tokio_util::sync::CancellationToken,
+) {
+    let state = app.state::<AppState>();
+    let run_id = run.id.clone();
+    let max_duration = tokio::time::Duration::from_secs(MAX_RUN_WATCH_SECS);
+
+    tokio::select! {
+        _ = cancel.cancelled() => tracing::info!("Stopped watching run {}: cancelled", run_id),
+        watched = tokio::time::timeout(max_duration, follow_run_progress(&app, &state, run)) => {
+            if watched.is_err() {
+                tracing::warn!(
+                    "Stopped watching run {}: still running after {}s",
+                    run_id,
+                    MAX_RUN_WATCH_SECS
+                );
+            }
+        }
+    }
+    state.finish_cancellation(&watch_id).await;
+}
+
+async fn follow_run_progress(app: &AppHandle, state: &AppState, mut run: TrainingRunResponse) {
+    let mut failures = 0;
+
+    loop {
+        if let Err(e) = app.emit("training-progress", &run) {
+            tracing::warn!("Failed to emit training progress for {}: {}", run.id, e);
+        }
+        if matches!(run.status.as_str(), "completed" | "failed" | "cancelled") {
+            return;
+        }
+
+        tokio::time::sleep(tokio::time::Duration::from_millis(RUN_WATCH_INTERVAL_MS)).await;
+
+        loop {
+            let polled = state.tinker.lock().await.get_training_run(&run.id).await;
+            match polled {
+                Ok(latest) => {
+                    failures = 0;
+                    run = latest.into();
+                    break;
+                }
+                Err(e) => {
+                    failures += 1;
+                    if failures >= MAX_RUN_WATCH_ERRORS {
+                        tracing::warn!("Stopped watching run {}: {}", run.id, e);
+                        return;
+                    }
+                    tokio::time::sleep(tokio::time::Duration::from_millis(RUN_WATCH_INTERVAL_MS))
+                        .await;
+                }
+            }
+        }
+    }
+}
+
+/// Validate, upload (for local files) and create a training run
+async fn start_training_run(
+    state: &AppState,
+    request: CreateTrainingRequest,
 ) -> Result<TrainingRunResponse, String> {
     let client = state.tinker.lock().await;
 
-    let training_type = match request.training_type.to_lowercase().as_str() {
-        "sft" => TrainingType::Sft,
-        "rl" => TrainingType::Rl,
-        "grpo" => TrainingType::Grpo,
-        "ppo" => TrainingType::Ppo,
-        "dpo" => TrainingType::Dpo,
-        "gkd" => TrainingType::Gkd,
-        _ => return Err(format!("Unknown training type: {}", request.training_type)),
+    let training_type = resolve_training_type(&request.training_type)?;
+
+    // A local file (JSONL, JSON or CSV) is normalized and checked against the
+    // training type before the (slow) upload, and the checked records are what
+    // gets uploaded, always as JSONL
+    let dataset_path = if std::path::Path::new(&request.dataset_id).is_file() {
+        let prepared = normalize_records(read_records(&request.dataset_id, None)?)?;
+        check_tinker_format(&prepared.records, &training_type).into_result()?;
+
+        let mut file_data = Vec::new();
+        write_jsonl(&mut file_data, &prepared.records).map_err(|e| e.to_string())?;
+        let filename = format!(
+            "{}.jsonl",
+            std::path::Path::new(&request.dataset_id)
+                .file_stem()
+                .and_then(|n| n.to_str())
+                .unwrap_or("dataset")
+        );
+
+        client
+            .upload_dataset(file_data, &filename)
+            .await
+            .map_err(|e| e.to_string())?
+            .path
+    } else {
+        request.dataset_id
+    };
+
+    let lora_config = match request.lora_config {
+        Some(l) => {
+            let model = find_model(&client, &request.model).await;
+            let check = resolve_target_modules(model.as_ref(), l.target_modules)?;
+            for correction in &check.corrections {
+                tracing::info!("LoRA target modules for {}: {}", request.model, correction);
+            }
+            Some(LoraConfig {
+                rank: l.rank,
+                alpha: l.alpha,
+                dropout: l.dropout,
+                target_modules: check.target_modules,
+            })
+        }
+        None => None,
     };
 
     let config = TrainingConfig {
         model: request.model,
         training_type,
-        dataset_path: request.dataset_id, // In real impl, this would be a path/URL
+        dataset_path,
         hyperparameters: Hyperparameters {
             learning_rate: request.hyperparameters.learning_rate,
             batch_size: request.hyperparameters.batch_size,
@@ -121,14 +427,7 @@ pub async fn create_training_run(
             weight_decay: request.hyperparameters.weight_decay,
             gradient_accumulation_steps: request.hyperparameters.gradient_accumulation_steps,
         },
-        lora_config: request.lora_config.map(|l| LoraConfig {
-            rank: l.rank,
-            alpha: l.alpha,
-            dropout: l.dropout,
-            target_modules: l.target_modules.unwrap_or_else(|| {
-                vec!["q_proj".to_string(), "v_proj".to_string()]
-            }),
-        }),
+        lora_config,
         name: request.name,
         description: request.description,
     };
@@ -146,6 +445,14 @@ pub async fn create_training_run(
 pub async fn get_training_run(
     state: State<'_, AppState>,
     run_id: String,
+) -> Result<TrainingRunResponse, String> {
+    let result = get_training_run_inner(&state, run_id).await;
+    state.capture("get_training_run", result).await
+}
+
+async fn get_training_run_inner(
+    state: &AppState,
+    run_id: String,
 ) -> Result<TrainingRunResponse, String> {
     let client = state.tinker.lock().await;
 
@@ -163,6 +470,15 @@ pub async fn list_training_runs(
     state: State<'_, AppState>,
     page: Option<u32>,
     per_page: Option<u32>,
+) -> Result<Vec<TrainingRunResponse>, String> {
+    let result = list_training_runs_inner(&state, page, per_page).await;
+    state.capture("list_training_runs", result).await
+}
+
+async fn list_training_runs_inner(
+    state: &AppState,
+    page: Option<u32>,
+    per_page: Option<u32>,
 ) -> Result<Vec<TrainingRunResponse>, String> {
     let client = state.tinker.lock().await;
 
@@ -188,6 +504,14 @@ pub async fn get_training_status(
 pub async fn cancel_training_run(
     state: State<'_, AppState>,
     run_id: String,
+) -> Result<TrainingRunResponse, String> {
+    let result = cancel_training_run_inner(&state, run_id).await;
+    state.capture("cancel_training_run", result).await
+}
+
+async fn cancel_training_run_inner(
+    state: &AppState,
+    run_id: String,
 ) -> Result<TrainingRunResponse, String> {
     let client = state.tinker.lock().await;
 
@@ -198,3 +522,1743 @@ pub async fn cancel_training_run(
 
     Ok(run.into())
 }
+
+// ============ Run Results ============
+
+/// Checkpoints fetched per page when looking for a run's final metrics
+const CHECKPOINTS_PER_PAGE: u32 = 100;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BestCheckpoint {
+    pub id: String,
+    pub step: u32,
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunResults {
+    pub run_id: String,
+    pub model: String,
+    pub training_type: String,
+    /// Training loss at the last checkpoint (or the last reported progress)
+    pub final_loss: Option<f64>,
+    /// Evaluation metrics of the best checkpoint
+    pub eval_loss: Option<f64>,
+    pub accuracy: Option<f64>,
+    /// Lowest eval loss, or lowest training loss when no eval was run
+    pub best_checkpoint: Option<BestCheckpoint>,
+    pub total_steps: Option<u32>,
+    pub completed_at: String,
+}
+
+/// Final metrics of a completed run, taken from its best checkpoint
+///
+/// Fails with a "not ready" error while the run is pending or running, and
+/// with the run's error if it failed or was cancelled.
+#[tauri::command]
+pub async fn get_run_results(
+    state: State<'_, AppState>,
+    run_id: String,
+) -> Result<RunResults, String> {
+    let result = get_run_results_inner(&state, run_id).await;
+    state.capture("get_run_results", result).await
+}
+
+async fn get_run_results_inner(state: &AppState, run_id: String) -> Result<RunResults, String> {
+    let client = state.tinker.lock().await;
+    let run = client.get_training_run(&run_id).await.map_err(|e| e.to_string())?;
+
+    let status = format!("{:?}", run.status).to_lowercase();
+    match run.status {
+        TrainingStatus::Completed => {}
+        TrainingStatus::Pending | TrainingStatus::Running => {
+            let progress = run
+                .progress
+                .as_ref()
+                .map(|p| format!(" (step {}/{})", p.current_step, p.total_steps))
+                .unwrap_or_default();
+            return Err(format!(
+                "Results for run {} are not ready: training is still {}{}",
+                run_id, status, progress
+            ));
+        }
+        TrainingStatus::Failed | TrainingStatus::Cancelled => {
+            return Err(format!(
+                "Run {} has no results: it {}{}",
+                run_id,
+                status,
+                run.error.map(|e| format!(": {}", e)).unwrap_or_default()
+            ));
+        }
+    }
+
+    let mut checkpoints = Vec::new();
+    let mut page = 1;
+    loop {
+        let list = client
+            .list_checkpoints(&run_id, Some(page), Some(CHECKPOINTS_PER_PAGE))
+            .await
+            .map_err(|e| e.to_string())?;
+        let fetched = list.checkpoints.len();
+        checkpoints.extend(list.checkpoints);
+        if fetched == 0 || checkpoints.len() >= list.total as usize {
+            break;
+        }
+        page += 1;
+    }
+
+    let scored: Vec<_> = checkpoints
+        .iter()
+        .filter_map(|c| c.metrics.as_ref().map(|m| (c, m)))
+        .collect();
+    let last = scored.iter().max_by_key(|(c, _)| c.step);
+    let best = scored
+        .iter()
+        .filter_map(|(c, m)| m.eval_loss.map(|eval| (c, m, eval)))
+        .min_by(|a, b| a.2.total_cmp(&b.2))
+        .map(|(c, m, _)| (*c, *m))
+        .or_else(|| {
+            scored
+                .iter()
+                .min_by(|(_, a), (_, b)| a.loss.total_cmp(&b.loss))
+                .copied()
+        });
+
+    Ok(RunResults {
+        final_loss: last
+            .map(|(_, m)| m.loss)
+            .or_else(|| run.progress.as_ref().and_then(|p| p.loss)),
+        eval_loss: best.and_then(|(_, m)| m.eval_loss),
+        accuracy: best.and_then(|(_, m)| m.accuracy),
+        best_checkpoint: best.map(|(c, _)| BestCheckpoint {
+            id: c.id.clone(),
+            step: c.step,
+            path: c.path.clone(),
+        }),
+        total_steps: run.progress.as_ref().map(|p| p.total_steps),
+        completed_at: run.updated_at.to_rfc3339(),
+        training_type: format!("{:?}", run.training_type).to_lowercase(),
+        model: run.model,
+        run_id,
+    })
+}
+
+// ============ LoRA Target Modules ============
+
+/// Modules targeted when the request doesn't specify any
+const DEFAULT_TARGET_MODULES: &[&str] = &["q_proj", "v_proj"];
+
+/// Separate query, key and value projections across architectures (Llama-style, BERT-style)
+const ATTENTION_ROLE_MODULES: &[&[&str]] = &[
+    &["q_proj", "query"],
+    &["k_proj", "key"],
+    &["v_proj", "value"],
+];
+/// Fused query/key/value projections (GPT-NeoX/Falcon, GPT-2, MPT)
+const FUSED_QKV_MODULES: &[&str] = &["query_key_value", "c_attn", "Wqkv"];
+
+/// Other groups of module names that play the same role across architectures
+const TARGET_MODULE_EQUIVALENTS: &[&[&str]] = &[
+    &["o_proj", "dense", "c_proj", "out_proj"],
+    &["gate_proj", "up_proj", "dense_h_to_4h", "c_fc", "fc1", "w1", "w3"],
+    &["down_proj", "dense_4h_to_h", "fc2", "w2"],
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TargetModuleCheck {
+    pub target_modules: Vec<String>,
+    /// Human-readable notes for each module that was substituted
+    pub corrections: Vec<String>,
+    /// Modules the model supports, empty when Tinker doesn't report them
+    pub valid_modules: Vec<String>,
+}
+
+/// Check LoRA target modules against a model, mapping names from other
+/// architectures onto the model's equivalents
+pub fn resolve_target_modules(
+    model: Option<&ModelInfo>,
+    requested: Option<Vec<String>>,
+) -> Result<TargetModuleCheck, String> {
+    let requested = requested
+        .filter(|modules| !modules.is_empty())
+        .unwrap_or_else(|| DEFAULT_TARGET_MODULES.iter().map(|m| m.to_string()).collect());
+    let valid_modules = model.map(|m| m.target_modules.clone()).unwrap_or_default();
+
+    // Without an architecture to check against, pass the request through
+    if valid_modules.is_empty() {
+        return Ok(TargetModuleCheck {
+            target_modules: requested,
+            corrections: Vec::new(),
+            valid_modules,
+        });
+    }
+
+    let model_id = model.map(|m| m.id.as_str()).unwrap_or_default();
+    let mut target_modules: Vec<String> = Vec::new();
+    let mut corrections = Vec::new();
+    for module in requested {
+        let resolved = if valid_modules.contains(&module) {
+            vec![module.clone()]
+        } else {
+            let equivalents = equivalent_modules(&module, &valid_modules).ok_or_else(|| {
+                format!(
+                    "LoRA target module '{}' doesn't exist on {}; valid modules: {}",
+                    module,
+                    model_id,
+                    valid_modules.join(", ")
+                )
+            })?;
+            corrections.push(format!("'{}' replaced with '{}'", module, equivalents.join("', '")));
+            equivalents
+        };
+        for module in resolved {
+            if !target_modules.contains(&module) {
+                target_modules.push(module);
+            }
+        }
+    }
+
+    Ok(TargetModuleCheck {
+        target_modules,
+        corrections,
+        valid_modules,
+    })
+}
+
+/// The model's modules playing the same role as `module`, if any
+///
+/// A separate q/k/v projection maps onto the same role first and onto a fused
+/// qkv module only when the model has no separate one; a fused module maps
+/// onto whichever separate projections the model has.
+fn equivalent_modules(module: &str, valid_modules: &[String]) -> Option<Vec<String>> {
+    let valid = |group: &[&str]| {
+        group
+            .iter()
+            .find(|m| valid_modules.iter().any(|v| v == *m))
+            .map(|m| m.to_string())
+    };
+
+    if let Some(role) = ATTENTION_ROLE_MODULES.iter().find(|group| group.contains(&module)) {
+        return valid(role).or_else(|| valid(FUSED_QKV_MODULES)).map(|m| vec![m]);
+    }
+    if FUSED_QKV_MODULES.contains(&module) {
+        if let Some(fused) = valid(FUSED_QKV_MODULES) {
+            return Some(vec![fused]);
+        }
+        let roles: Vec<String> = ATTENTION_ROLE_MODULES.iter().filter_map(|g| valid(g)).collect();
+        return (!roles.is_empty()).then_some(roles);
+    }
+    TARGET_MODULE_EQUIVALENTS
+        .iter()
+        .find(|group| group.contains(&module))
+        .and_then(|group| valid(group))
+        .map(|m| vec![m])
+}
+
+/// Validate LoRA target modules for a model, returning the corrected list
+#[tauri::command]
+pub async fn validate_target_modules(
+    state: State<'_, AppState>,
+    model: String,
+    target_modules: Option<Vec<String>>,
+) -> Result<TargetModuleCheck, String> {
+    let result = validate_target_modules_inner(&state, model, target_modules).await;
+    state.capture("validate_target_modules", result).await
+}
+
+async fn validate_target_modules_inner(
+    state: &AppState,
+    model: String,
+    target_modules: Option<Vec<String>>,
+) -> Result<TargetModuleCheck, String> {
+    let info = find_model(&*state.tinker.lock().await, &model).await;
+    resolve_target_modules(info.as_ref(), target_modules)
+}
+
+// ============ Cost Estimation ============
+
+/// Fallback Tinker price when the model list can't be fetched
+const DEFAULT_PRICE_PER_MILLION_TOKENS: f64 = 2.0;
+/// Fallback dataset size per example when a generation preview is unavailable
+const DEFAULT_TOKENS_PER_EXAMPLE: u32 = 256;
+const DEFAULT_NUM_EPOCHS: u32 = 3;
+
+/// Estimate Tinker training cost: every dataset token is processed once per epoch
+pub fn estimate_training_cost(dataset_tokens: u64, num_epochs: u32, price_per_million_tokens: f64) -> f64 {
+    dataset_tokens as f64 * num_epochs as f64 * price_per_million_tokens / 1_000_000.0
+}
+
+/// Look up a model's Tinker price, falling back to a default when unavailable
+pub async fn model_price(state: &AppState, model: &str) -> f64 {
+    let client = state.tinker.lock().await;
+    match client.get_models().await {
+        Ok(models) => models
+            .into_iter()
+            .find(|m| m.id == model)
+            .map(|m| m.price_per_million_tokens)
+            .unwrap_or(DEFAULT_PRICE_PER_MILLION_TOKENS),
+        Err(e) => {
+            tracing::debug!("Using default model price: {}", e);
+            DEFAULT_PRICE_PER_MILLION_TOKENS
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SweepPoint {
+    pub num_examples: u32,
+    pub estimated_tokens: u32,
+    pub generation_cost: Option<f64>,
+    pub training_cost: f64,
+    pub total_cost: f64,
+    /// Set when the Tonic preview failed (token count falls back to a default)
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExampleCountSweep {
+    pub model: String,
+    pub num_epochs: u32,
+    pub price_per_million_tokens: f64,
+    pub points: Vec<SweepPoint>,
+    /// Minimum example count suggested by research, if any
+    pub recommended_minimum: Option<u32>,
+}
+
+/// Estimate generation + training cost across candidate example counts
+#[tauri::command]
+pub async fn sweep_example_counts(
+    state: State<'_, AppState>,
+    intent: TrainingIntent,
+    counts: Vec<u32>,
+    num_epochs: Option<u32>,
+    research: Option<ResearchResponse>,
+) -> Result<ExampleCountSweep, String> {
+    let result = sweep_example_counts_inner(&state, intent, counts, num_epochs, research).await;
+    state.capture("sweep_example_counts", result).await
+}
+
+async fn sweep_example_counts_inner(
+    state: &AppState,
+    intent: TrainingIntent,
+    counts: Vec<u32>,
+    num_epochs: Option<u32>,
+    research: Option<ResearchResponse>,
+) -> Result<ExampleCountSweep, String> {
+    if counts.is_empty() {
+        return Err("At least one example count is required".to_string());
+    }
+
+    let model = intent
+        .suggested_model
+        .clone()
+        .unwrap_or_else(|| "llama-3-8b".to_string());
+    let num_epochs = num_epochs.unwrap_or(DEFAULT_NUM_EPOCHS);
+    let price = model_price(state, &model).await;
+
+    let mut counts = counts;
+    counts.sort_unstable();
+    counts.dedup();
+
+    let tonic_client = state.tonic.lock().await;
+    let mut points = Vec::with_capacity(counts.len());
+    for count in counts {
+        let prompt = tonic::training_data_prompt(
+            &intent.task_description,
+            &intent.domain,
+            count,
+            intent.style.as_deref(),
+        );
+
+        let (estimated_tokens, generation_cost, error) =
+            match tonic_client.preview_generation(&prompt, count).await {
+                Ok(preview) => (preview.estimated_tokens, Some(preview.estimated_cost), None),
+                Err(e) => {
+                    let tokens = count.saturating_mul(DEFAULT_TOKENS_PER_EXAMPLE);
+                    (tokens, None, Some(e.to_string()))
+                }
+            };
+
+        let training_cost = estimate_training_cost(estimated_tokens as u64, num_epochs, price);
+        points.push(SweepPoint {
+            num_examples: count,
+            estimated_tokens,
+            generation_cost,
+            training_cost,
+            total_cost: generation_cost.unwrap_or(0.0) + training_cost,
+            error,
+        });
+    }
+
+    Ok(ExampleCountSweep {
+        model,
+        num_epochs,
+        price_per_million_tokens: price,
+        points,
+        recommended_minimum: research.as_ref().and_then(research_minimum_examples),
+    })
+}
+
+/// Pull an example-count recommendation out of research params, if one was found
+fn research_minimum_examples(research: &ResearchResponse) -> Option<u32> {
+    research
+        .recommended_params
+        .iter()
+        .filter(|p| {
+            let text = format!("{} {}", p.name, p.rationale).to_lowercase();
+            text.contains("examples") || text.contains("samples")
+        })
+        .find_map(|p| p.value.replace(',', "").parse::<f64>().ok())
+        .map(|v| v as u32)
+}
+
+// ============ Model Recommendation ============
+
+/// Examples per billion parameters at which a model size is considered a good fit
+const EXAMPLES_PER_BILLION_PARAMS: f64 = 50.0;
+/// Candidates sent to Claude for refinement
+const MAX_REFINED_CANDIDATES: usize = 5;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelCandidate {
+    pub model_id: String,
+    pub name: String,
+    pub parameters_billions: Option<f64>,
+    pub estimated_cost: f64,
+    pub within_budget: bool,
+    /// Heuristic fit, 0-1 (higher is better)
+    pub score: f64,
+    pub rationale: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelRecommendation {
+    pub recommended: Option<String>,
+    pub training_type: String,
+    /// Best first
+    pub candidates: Vec<ModelCandidate>,
+    pub rationale: String,
+    /// Claude picked `recommended`; otherwise the heuristic ranking was used
+    pub refined: bool,
+}
+
+/// Rank Tinker models for a task by size fit and cost, optionally refined by Claude
+#[tauri::command]
+pub async fn recommend_model(
+    state: State<'_, AppState>,
+    intent: TrainingIntent,
+    data_stats: DataStats,
+    max_cost: Option<f64>,
+) -> Result<ModelRecommendation, String> {
+    let result = recommend_model_inner(&state, intent, data_stats, max_cost).await;
+    state.capture("recommend_model", result).await
+}
+
+async fn recommend_model_inner(
+    state: &AppState,
+    intent: TrainingIntent,
+    data_stats: DataStats,
+    max_cost: Option<f64>,
+) -> Result<ModelRecommendation, String> {
+    let training_type = match intent.suggested_training_type.as_deref() {
+        Some(name) => resolve_training_type(name)?,
+        None => TrainingType::Sft,
+    };
+
+    let models = {
+        let client = state.tinker.lock().await;
+        client.get_models().await.map_err(|e| e.to_string())?
+    };
+
+    let ideal_billions = ideal_model_billions(data_stats.num_samples);
+    let candidates = rank_models(models, &training_type, &data_stats, max_cost);
+
+    let mut recommendation = ModelRecommendation {
+        recommended: candidates.first().filter(|c| c.within_budget).map(|c| c.model_id.clone()),
+        training_type: training_type.as_str().to_string(),
+        rationale: String::new(),
+        candidates,
+        refined: false,
+    };
+    recommendation.rationale = match recommendation.candidates.first() {
+        None => format!("No Tinker model supports {} training", training_type.as_str()),
+        Some(best) if best.within_budget => format!(
+            "{} best balances size for {} examples (~{:.0}B ideal) against cost",
+            best.model_id, data_stats.num_samples, ideal_billions
+        ),
+        Some(_) => "No supported model fits within the budget".to_string(),
+    };
+
+    let has_anthropic_key = state.anthropic.lock().await.has_api_key();
+    if has_anthropic_key && recommendation.recommended.is_some() {
+        if let Err(e) = refine_model_choice(state, &intent, &mut recommendation).await {
+            tracing::warn!("Model refinement failed, using heuristic ranking: {}", e);
+        }
+    }
+
+    Ok(recommendation)
+}
+
+/// Model size (billions of parameters) that best fits a dataset of this many examples
+fn ideal_model_billions(num_samples: u32) -> f64 {
+    (num_samples as f64 / EXAMPLES_PER_BILLION_PARAMS).clamp(1.0, 70.0)
+}
+
+/// Score models that support a training type, in-budget first and best first
+fn rank_models(
+    models: Vec<ModelInfo>,
+    training_type: &TrainingType,
+    data_stats: &DataStats,
+    max_cost: Option<f64>,
+) -> Vec<ModelCandidate> {
+    let dataset_tokens =
+        data_stats.num_samples as u64 * data_stats.avg_tokens_per_sample.max(1) as u64;
+    let ideal_billions = ideal_model_billions(data_stats.num_samples);
+
+    let mut candidates: Vec<ModelCandidate> = models
+        .into_iter()
+        .filter(|m| {
+            m.supported_training_types
+                .iter()
+                .any(|t| t.as_str() == training_type.as_str())
+        })
+        .map(|m| score_model(&m, dataset_tokens, ideal_billions, max_cost))
+        .collect();
+    candidates.sort_by(|a, b| {
+        b.within_budget
+            .cmp(&a.within_budget)
+            .then(b.score.total_cmp(&a.score))
+    });
+    candidates
+}
+
+fn score_model(
+    model: &ModelInfo,
+    dataset_tokens: u64,
+    ideal_billions: f64,
+    max_cost: Option<f64>,
+) -> ModelCandidate {
+    let parameters_billions = parse_parameter_count(&model.parameters)
+        .or_else(|| parse_parameter_count(&model.id))
+        .map(|p| p / 1e9);
+    let estimated_cost =
+        estimate_training_cost(dataset_tokens, DEFAULT_NUM_EPOCHS, model.price_per_million_tokens);
+    let within_budget = max_cost.is_none_or(|max| estimated_cost <= max);
+
+    // Penalize distance from the ideal size on a log scale; unknown sizes score mid-range
+    let size_score = parameters_billions
+        .map(|p| 1.0 - ((p / ideal_billions).ln().abs() / 70f64.ln()).min(1.0))
+        .unwrap_or(0.5);
+    let reference_cost = max_cost.unwrap_or(10.0).max(0.01);
+    let cost_score = 1.0 / (1.0 + estimated_cost / reference_cost);
+    let score = 0.6 * size_score + 0.4 * cost_score;
+
+    let size = parameters_billions
+        .map(|p| format!("{:.1}B params", p))
+        .unwrap_or_else(|| "unknown size".to_string());
+    let rationale = format!(
+        "{}, ~${:.2} for {} epochs{}",
+        size,
+        estimated_cost,
+        DEFAULT_NUM_EPOCHS,
+        if within_budget { "" } else { ", over budget" }
+    );
+
+    ModelCandidate {
+        model_id: model.id.clone(),
+        name: model.name.clone(),
+        parameters_billions,
+        estimated_cost,
+        within_budget,
+        score,
+        rationale,
+    }
+}
+
+/// Let the Config agent pick among the top in-budget candidates
+async fn refine_model_choice(
+    state: &AppState,
+    intent: &TrainingIntent,
+    recommendation: &mut ModelRecommendation,
+) -> Result<(), String> {
+    let shortlist: Vec<&ModelCandidate> = recommendation
+        .candidates
+        .iter()
+        .filter(|c| c.within_budget)
+        .take(MAX_REFINED_CANDIDATES)
+        .collect();
+    let options = shortlist
+        .iter()
+        .map(|c| format!("- {} ({})", c.model_id, c.rationale))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let prompt = format!(
+        "Pick the best base model for this fine-tuning task.\n\nTask: {}\nDomain: {}\n\
+         Constraints: {}\n\nCandidates:\n{}\n\n\
+         Respond with JSON only: {{\"model\": \"<candidate id>\", \"rationale\": \"...\"}}",
+        intent.task_description,
+        intent.domain,
+        intent.constraints.join("; "),
+        options
+    );
+
+    let response = {
+        let client = state.anthropic.lock().await;
+        client
+            .chat_with_agent(AgentType::Config, &prompt)
+            .await
+            .map_err(|e| e.to_string())?
+    };
+    state.record_usage(AgentType::Config, response.usage.as_ref()).await;
+    response.ensure_complete().map_err(|e| e.to_string())?;
+
+    let json_str = anthropic::extract_json(&response.content).map_err(|e| e.to_string())?;
+    let choice: Value = serde_json::from_str(&json_str).map_err(|e| e.to_string())?;
+    let model = choice["model"].as_str().ok_or("Response has no model")?;
+    let position = recommendation
+        .candidates
+        .iter()
+        .position(|c| c.model_id == model && c.within_budget)
+        .ok_or_else(|| format!("Agent picked an unknown model: {}", model))?;
+
+    let chosen = recommendation.candidates.remove(position);
+    recommendation.recommended = Some(chosen.model_id.clone());
+    recommendation.candidates.insert(0, chosen);
+    if let Some(rationale) = choice["rationale"].as_str() {
+        recommendation.rationale = rationale.to_string();
+    }
+    recommendation.refined = true;
+    Ok(())
+}
+
+// ============ Workflow Planning ============
+
+/// Example count when the intent doesn't suggest one
+const DEFAULT_EXAMPLE_COUNT: u32 = 1000;
+/// Typical Yutori research turnaround
+const RESEARCH_MINUTES: u32 = 5;
+/// Rough Tinker training throughput, used only for the time estimate
+const TRAINING_TOKENS_PER_SECOND: f64 = 2000.0;
+/// Model used when neither the intent nor the Tinker model list names one
+const FALLBACK_MODEL: &str = "llama-3-8b";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanStep {
+    /// "research", "generate_data" or "train"
+    pub stage: String,
+    pub description: String,
+    /// The step won't run (e.g. no API key, or the user supplies the data)
+    pub skipped: bool,
+    pub estimated_cost: Option<f64>,
+    pub estimated_minutes: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowPlan {
+    /// In execution order
+    pub steps: Vec<PlanStep>,
+    pub runs_research: bool,
+    /// 0 when the user supplies their own dataset
+    pub examples_to_generate: u32,
+    pub model: String,
+    pub training_type: String,
+    pub num_epochs: u32,
+    pub estimated_tokens: u32,
+    pub estimated_cost: f64,
+    pub estimated_minutes: u32,
+    /// Assumptions made because an estimate or recommendation was unavailable
+    pub warnings: Vec<String>,
+}
+
+/// Preview the research, data generation and training steps an intent would
+/// run, with cost and time estimates, without starting any of them
+#[tauri::command]
+pub async fn plan_workflow(
+    state: State<'_, AppState>,
+    intent: TrainingIntent,
+) -> Result<WorkflowPlan, String> {
+    let result = plan_workflow_inner(&state, intent).await;
+    state.capture("plan_workflow", result).await
+}
+
+async fn plan_workflow_inner(
+    state: &AppState,
+    intent: TrainingIntent,
+) -> Result<WorkflowPlan, String> {
+    let training_type = match intent.suggested_training_type.as_deref() {
+        Some(name) => resolve_training_type(name)?,
+        None => TrainingType::Sft,
+    };
+    let num_examples = intent.suggested_example_count.unwrap_or(DEFAULT_EXAMPLE_COUNT);
+    let mut warnings = Vec::new();
+    let mut steps = Vec::new();
+
+    let runs_research = state.yutori.lock().await.has_api_key();
+    steps.push(PlanStep {
+        stage: "research".to_string(),
+        description: if runs_research {
+            format!(
+                "Research best practices for {} in {}",
+                intent.task_description, intent.domain
+            )
+        } else {
+            "Skip research (Yutori API key not configured)".to_string()
+        },
+        skipped: !runs_research,
+        estimated_cost: None,
+        estimated_minutes: runs_research.then_some(RESEARCH_MINUTES),
+    });
+
+    let mut estimated_tokens = num_examples.saturating_mul(DEFAULT_TOKENS_PER_EXAMPLE);
+    let mut generation = (None, None);
+    if intent.needs_synthetic_data {
+        let prompt = tonic::training_data_prompt(
+            &intent.task_description,
+            &intent.domain,
+            num_examples,
+            intent.style.as_deref(),
+        );
+        match state.tonic.lock().await.preview_generation(&prompt, num_examples).await {
+            Ok(preview) => {
+                estimated_tokens = preview.estimated_tokens;
+                generation = (
+                    Some(preview.estimated_cost),
+                    Some(preview.estimated_duration_seconds.div_ceil(60)),
+                );
+            }
+            Err(e) => warnings.push(format!(
+                "Generation preview unavailable ({}); assuming {} tokens per example",
+                e, DEFAULT_TOKENS_PER_EXAMPLE
+            )),
+        }
+    } else {
+        warnings.push(format!(
+            "Dataset size assumed at {} tokens per example until data is uploaded",
+            DEFAULT_TOKENS_PER_EXAMPLE
+        ));
+    }
+    steps.push(PlanStep {
+        stage: "generate_data".to_string(),
+        description: if intent.needs_synthetic_data {
+            format!("Generate {} synthetic examples with Tonic", num_examples)
+        } else {
+            "Use the uploaded dataset".to_string()
+        },
+        skipped: !intent.needs_synthetic_data,
+        estimated_cost: generation.0,
+        estimated_minutes: generation.1,
+    });
+
+    let models = match state.tinker.lock().await.get_models().await {
+        Ok(models) => models,
+        Err(e) => {
+            warnings.push(format!("Tinker model list unavailable: {}", e));
+            Vec::new()
+        }
+    };
+    let model = match intent.suggested_model.clone() {
+        Some(model) => model,
+        None => {
+            let data_stats = DataStats {
+                num_samples: num_examples,
+                avg_tokens_per_sample: estimated_tokens / num_examples.max(1),
+                max_tokens: 0,
+                min_tokens: 0,
+            };
+            match rank_models(models.clone(), &training_type, &data_stats, None).first() {
+                Some(best) => best.model_id.clone(),
+                None => {
+                    warnings.push(format!(
+                        "No model recommendation available; defaulting to {}",
+                        FALLBACK_MODEL
+                    ));
+                    FALLBACK_MODEL.to_string()
+                }
+            }
+        }
+    };
+    let price = models
+        .iter()
+        .find(|m| m.id == model)
+        .map(|m| m.price_per_million_tokens)
+        .unwrap_or(DEFAULT_PRICE_PER_MILLION_TOKENS);
+
+    let training_cost =
+        estimate_training_cost(estimated_tokens as u64, DEFAULT_NUM_EPOCHS, price);
+    let training_minutes = (estimated_tokens as f64 * DEFAULT_NUM_EPOCHS as f64
+        / TRAINING_TOKENS_PER_SECOND
+        / 60.0)
+        .ceil() as u32;
+    steps.push(PlanStep {
+        stage: "train".to_string(),
+        description: format!(
+            "{} fine-tune {} for {} epochs",
+            training_type.as_str().to_uppercase(),
+            model,
+            DEFAULT_NUM_EPOCHS
+        ),
+        skipped: false,
+        estimated_cost: Some(training_cost),
+        estimated_minutes: Some(training_minutes.max(1)),
+    });
+
+    let active = steps.iter().filter(|s| !s.skipped);
+    let estimated_cost = active.clone().filter_map(|s| s.estimated_cost).sum();
+    let estimated_minutes = active.filter_map(|s| s.estimated_minutes).sum();
+
+    Ok(WorkflowPlan {
+        steps,
+        runs_research,
+        examples_to_generate: if intent.needs_synthetic_data { num_examples } else { 0 },
+        model,
+        training_type: training_type.as_str().to_string(),
+        num_epochs: DEFAULT_NUM_EPOCHS,
+        estimated_tokens,
+        estimated_cost,
+        estimated_minutes,
+        warnings,
+    })
+}
+
+// ============ Config Templates ============
+
+/// Store key prefix for saved training config templates
+const CONFIG_TEMPLATE_PREFIX: &str = "config_template:";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigTemplate {
+    pub name: String,
+    pub request: CreateTrainingRequest,
+    pub created_at: String,
+    /// Tuning decisions recorded with `config_diff`, oldest first
+    #[serde(default)]
+    pub changelog: Vec<ConfigChangelogEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigFieldChange {
+    #[serde(flatten)]
+    pub change: ConfigChange,
+    /// The part of the agent's rationale that explains this change
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigChangelogEntry {
+    pub changes: Vec<ConfigFieldChange>,
+    /// One line per change, e.g. "hyperparameters.num_epochs: 3 -> 2 (...)"
+    pub summary: String,
+    pub created_at: String,
+}
+
+/// A stored template, or the reason it could no longer be loaded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigTemplateEntry {
+    pub name: String,
+    pub template: Option<ConfigTemplate>,
+    pub error: Option<String>,
+}
+
+/// Check a training request for values Tinker would reject
+pub fn validate_training_request(request: &CreateTrainingRequest) -> Result<(), String> {
+    resolve_training_type(&request.training_type)?;
+
+    if request.model.trim().is_empty() {
+        return Err("Model is required".to_string());
+    }
+
+    let hp = &request.hyperparameters;
+    if !(hp.learning_rate > 0.0 && hp.learning_rate.is_finite()) {
+        return Err(format!("Invalid learning rate: {}", hp.learning_rate));
+    }
+    if hp.batch_size == 0 {
+        return Err("Batch size must be greater than 0".to_string());
+    }
+    if hp.num_epochs == 0 {
+        return Err("Number of epochs must be greater than 0".to_string());
+    }
+
+    if let Some(lora) = &request.lora_config {
+        if lora.rank == 0 {
+            return Err("LoRA rank must be greater than 0".to_string());
+        }
+        if !(0.0..1.0).contains(&lora.dropout) {
+            return Err(format!("LoRA dropout must be in [0, 1): {}", lora.dropout));
+        }
+    }
+
+    Ok(())
+}
+
+/// Save a training config as a named, reusable template
+#[tauri::command]
+pub async fn save_config_template(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    name: String,
+    request: CreateTrainingRequest,
+) -> Result<ConfigTemplate, String> {
+    let result = save_config_template_inner(app, name, request).await;
+    state.capture("save_config_template", result).await
+}
+
+async fn save_config_template_inner(
+    app: AppHandle,
+    name: String,
+    request: CreateTrainingRequest,
+) -> Result<ConfigTemplate, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Template name cannot be empty".to_string());
+    }
+    validate_training_request(&request)?;
+
+    let template = ConfigTemplate {
+        name: name.clone(),
+        request,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        changelog: Vec::new(),
+    };
+
+    let store = app.store(STORE_FILE).map_err(|e| e.to_string())?;
+    let value = serde_json::to_value(&template).map_err(|e| e.to_string())?;
+    store.set(format!("{}{}", CONFIG_TEMPLATE_PREFIX, name), value);
+    store.save().map_err(|e| e.to_string())?;
+
+    Ok(template)
+}
+
+/// List saved templates, reporting any that no longer load or validate
+#[tauri::command]
+pub async fn list_config_templates(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Vec<ConfigTemplateEntry>, String> {
+    let result = list_config_templates_inner(app).await;
+    state.capture("list_config_templates", result).await
+}
+
+async fn list_config_templates_inner(app: AppHandle) -> Result<Vec<ConfigTemplateEntry>, String> {
+    let store = app.store(STORE_FILE).map_err(|e| e.to_string())?;
+
+    let mut entries: Vec<ConfigTemplateEntry> = store
+        .keys()
+        .into_iter()
+        .filter_map(|key| {
+            let name = key.strip_prefix(CONFIG_TEMPLATE_PREFIX)?.to_string();
+            let value = store.get(&key)?;
+            Some(match load_template(value) {
+                Ok(template) => ConfigTemplateEntry {
+                    name,
+                    template: Some(template),
+                    error: None,
+                },
+                Err(e) => ConfigTemplateEntry {
+                    name,
+                    template: None,
+                    error: Some(e),
+                },
+            })
+        })
+        .collect();
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(entries)
+}
+
+/// Build a training request from a template, with optional field overrides
+/// (merged as JSON, e.g. `{"dataset_id": "...", "hyperparameters": {"num_epochs": 2}}`)
+#[tauri::command]
+pub async fn apply_config_template(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    name: String,
+    overrides: Option<Value>,
+) -> Result<CreateTrainingRequest, String> {
+    let result = apply_config_template_inner(app, name, overrides).await;
+    state.capture("apply_config_template", result).await
+}
+
+async fn apply_config_template_inner(
+    app: AppHandle,
+    name: String,
+    overrides: Option<Value>,
+) -> Result<CreateTrainingRequest, String> {
+    let store = app.store(STORE_FILE).map_err(|e| e.to_string())?;
+    let value = store
+        .get(format!("{}{}", CONFIG_TEMPLATE_PREFIX, name.trim()))
+        .ok_or_else(|| format!("Config template not found: {}", name))?;
+    let template = load_template(value)?;
+
+    let Some(overrides) = overrides else {
+        return Ok(template.request);
+    };
+
+    let mut merged = serde_json::to_value(&template.request).map_err(|e| e.to_string())?;
+    merge_json(&mut merged, overrides);
+    let request: CreateTrainingRequest =
+        serde_json::from_value(merged).map_err(|e| format!("Invalid overrides: {}", e))?;
+    validate_training_request(&request)?;
+
+    Ok(request)
+}
+
+/// Describe what changed between two config recommendations and why, optionally
+/// appending the entry to a saved template's changelog
+#[tauri::command]
+pub async fn config_diff(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    before: ConfigRecommendation,
+    after: ConfigRecommendation,
+    template_name: Option<String>,
+) -> Result<ConfigChangelogEntry, String> {
+    let result = config_diff_inner(app, before, after, template_name).await;
+    state.capture("config_diff", result).await
+}
+
+async fn config_diff_inner(
+    app: AppHandle,
+    before: ConfigRecommendation,
+    after: ConfigRecommendation,
+    template_name: Option<String>,
+) -> Result<ConfigChangelogEntry, String> {
+    let before_json = serde_json::to_value(&before).map_err(|e| e.to_string())?;
+    let after_json = serde_json::to_value(&after).map_err(|e| e.to_string())?;
+    let mut diff = Vec::new();
+    agents::diff_json("", &before_json, &after_json, &mut diff);
+
+    let changes: Vec<ConfigFieldChange> = diff
+        .into_iter()
+        .filter(|c| c.field != "rationale")
+        .map(|change| ConfigFieldChange {
+            reason: change_reason(&change.field, &after.rationale),
+            change,
+        })
+        .collect();
+
+    let summary = changes
+        .iter()
+        .map(|c| {
+            let mut line =
+                format!("{}: {} -> {}", c.change.field, c.change.before, c.change.after);
+            if let Some(reason) = &c.reason {
+                line.push_str(&format!(" ({})", reason));
+            }
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let entry = ConfigChangelogEntry {
+        changes,
+        summary,
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+
+    if let Some(name) = template_name {
+        let store = app.store(STORE_FILE).map_err(|e| e.to_string())?;
+        let key = format!("{}{}", CONFIG_TEMPLATE_PREFIX, name.trim());
+        let value = store
+            .get(&key)
+            .ok_or_else(|| format!("Config template not found: {}", name))?;
+        let mut template = load_template(value)?;
+        template.changelog.push(entry.clone());
+        store.set(key, serde_json::to_value(&template).map_err(|e| e.to_string())?);
+        store.save().map_err(|e| e.to_string())?;
+    }
+
+    Ok(entry)
+}
+
+/// Pick the rationale sentence that mentions a changed field, if any
+fn change_reason(field: &str, rationale: &str) -> Option<String> {
+    let keywords: &[&str] = match field.rsplit('.').next().unwrap_or(field) {
+        "learning_rate" => &["learning rate"],
+        "batch_size" => &["batch"],
+        "num_epochs" => &["epoch"],
+        "warmup_steps" => &["warmup"],
+        "rank" => &["rank"],
+        "alpha" => &["alpha"],
+        "dropout" => &["dropout"],
+        "model" => &["model"],
+        "training_type" => &["training type"],
+        "estimated_cost" => &["cost", "$"],
+        "estimated_time_minutes" => &["time", "minute", "hour"],
+        _ => &[],
+    };
+
+    rationale
+        .split_inclusive(['.', '!', '?', '\n'])
+        .map(str::trim)
+        .find(|sentence| {
+            let lower = sentence.to_lowercase();
+            keywords.iter().any(|k| lower.contains(k))
+        })
+        .map(|sentence| sentence.trim_end_matches(['.', '\n']).to_string())
+}
+
+fn load_template(value: Value) -> Result<ConfigTemplate, String> {
+    let template: ConfigTemplate =
+        serde_json::from_value(value).map_err(|e| format!("Template no longer matches schema: {}", e))?;
+    validate_training_request(&template.request)?;
+    Ok(template)
+}
+
+/// Recursively merge `patch` into `target`, replacing non-object values
+pub fn merge_json(target: &mut Value, patch: Value) {
+    match (target, patch) {
+        (Value::Object(target), Value::Object(patch)) => {
+            for (key, value) in patch {
+                merge_json(target.entry(key).or_insert(Value::Null), value);
+            }
+        }
+        (target, patch) => *target = patch,
+    }
+}
+
+// ============ Share Codes ============
+
+/// Prefix identifying (and versioning) a run config share code
+const SHARE_CODE_PREFIX: &str = "tvcfg1:";
+
+/// Encode a run's configuration as a compact, copy-pasteable share code
+#[tauri::command]
+pub async fn export_run_config_code(
+    state: State<'_, AppState>,
+    run_id: String,
+) -> Result<String, String> {
+    let result = export_run_config_code_inner(&state, run_id).await;
+    state.capture("export_run_config_code", result).await
+}
+
+async fn export_run_config_code_inner(state: &AppState, run_id: String) -> Result<String, String> {
+    let run = {
+        let client = state.tinker.lock().await;
+        client.get_training_run(&run_id).await.map_err(|e| e.to_string())?
+    };
+    let config = run
+        .config
+        .ok_or_else(|| format!("Run {} has no recorded configuration", run_id))?;
+
+    let request = CreateTrainingRequest {
+        name: config.name,
+        description: config.description,
+        model: config.model,
+        training_type: config.training_type.as_str().to_string(),
+        dataset_id: config.dataset_path,
+        hyperparameters: HyperparametersInput {
+            learning_rate: config.hyperparameters.learning_rate,
+            batch_size: config.hyperparameters.batch_size,
+            num_epochs: config.hyperparameters.num_epochs,
+            max_steps: config.hyperparameters.max_steps,
+            warmup_steps: config.hyperparameters.warmup_steps,
+            weight_decay: config.hyperparameters.weight_decay,
+            gradient_accumulation_steps: config.hyperparameters.gradient_accumulation_steps,
+        },
+        lora_config: config.lora_config.map(|l| LoraConfigInput {
+            rank: l.rank,
+            alpha: l.alpha,
+            dropout: l.dropout,
+            target_modules: Some(l.target_modules),
+        }),
+    };
+
+    let json = serde_json::to_vec(&request).map_err(|e| e.to_string())?;
+    Ok(format!("{}{}", SHARE_CODE_PREFIX, BASE64_URL.encode(json)))
+}
+
+/// Decode a share code back into a validated training request
+#[tauri::command]
+pub async fn import_run_config_code(
+    state: State<'_, AppState>,
+    code: String,
+) -> Result<CreateTrainingRequest, String> {
+    let result = import_run_config_code_inner(code).await;
+    state.capture("import_run_config_code", result).await
+}
+
+async fn import_run_config_code_inner(code: String) -> Result<CreateTrainingRequest, String> {
+    let encoded = code
+        .trim()
+        .strip_prefix(SHARE_CODE_PREFIX)
+        .ok_or("Not a run config share code")?;
+    let json = BASE64_URL
+        .decode(encoded)
+        .map_err(|e| format!("Malformed share code: {}", e))?;
+    let request: CreateTrainingRequest = serde_json::from_slice(&json)
+        .map_err(|e| format!("Malformed share code: {}", e))?;
+
+    validate_training_request(&request)?;
+    Ok(request)
+}
+
+// ============ Resource Estimation ============
+
+/// Bytes per parameter for bf16 weights
+const BYTES_PER_WEIGHT: f64 = 2.0;
+/// Bytes per trainable parameter: bf16 weight + bf16 grad + fp32 master + two fp32 Adam moments
+const BYTES_PER_TRAINABLE_PARAM: f64 = 16.0;
+/// Bytes of activations kept per token per hidden unit per layer (with checkpointing)
+const ACTIVATION_BYTES_PER_UNIT: f64 = 4.0;
+/// Fixed CUDA context / allocator overhead
+const BASE_OVERHEAD_GB: f64 = 1.0;
+/// Proportional fragmentation overhead
+const OVERHEAD_FRACTION: f64 = 0.1;
+/// Attention/MLP projections per transformer layer that LoRA can target
+const PROJECTIONS_PER_LAYER: f64 = 7.0;
+/// Common single-GPU memory sizes
+const GPU_SIZES: &[(&str, f64)] = &[
+    ("RTX 4090 (24GB)", 24.0),
+    ("A100 (40GB)", 40.0),
+    ("A100/H100 (80GB)", 80.0),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VramBreakdown {
+    pub weights_gb: f64,
+    pub trainable_state_gb: f64,
+    pub activations_gb: f64,
+    pub overhead_gb: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GpuFit {
+    pub gpu: String,
+    pub memory_gb: f64,
+    pub fits: bool,
+    /// GPUs of this size needed if the model were sharded evenly
+    pub gpus_needed: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceEstimate {
+    pub model_id: String,
+    pub parameters_billions: f64,
+    pub trainable_parameters_millions: f64,
+    pub estimated_vram_gb: f64,
+    pub breakdown: VramBreakdown,
+    pub gpu_fit: Vec<GpuFit>,
+    /// How the estimate was derived
+    pub rationale: String,
+}
+
+/// Rough VRAM estimate for fine-tuning a model with the given LoRA and batch settings
+#[tauri::command]
+pub async fn estimate_resources(
+    state: State<'_, AppState>,
+    model_id: String,
+    lora_config: Option<LoraConfigInput>,
+    seq_length: u32,
+    batch_size: u32,
+) -> Result<ResourceEstimate, String> {
+    let result = estimate_resources_inner(
+        &state,
+        model_id,
+        lora_config,
+        seq_length,
+        batch_size,
+    )
+    .await;
+    state.capture("estimate_resources", result).await
+}
+
+async fn estimate_resources_inner(
+    state: &AppState,
+    model_id: String,
+    lora_config: Option<LoraConfigInput>,
+    seq_length: u32,
+    batch_size: u32,
+) -> Result<ResourceEstimate, String> {
+    if seq_length == 0 || batch_size == 0 {
+        return Err("Sequence length and batch size must be greater than 0".to_string());
+    }
+
+    let model = find_model(&*state.tinker.lock().await, &model_id).await;
+    let parameters = model
+        .as_ref()
+        .and_then(|m| parse_parameter_count(&m.parameters))
+        .or_else(|| parse_parameter_count(&model_id))
+        .ok_or_else(|| format!("Can't determine the parameter count of {}", model_id))?;
+
+    // Dense transformer: params ~ 12 * layers * hidden^2, with hidden ~ 128 * layers
+    let layers = (parameters / (12.0 * 128.0 * 128.0)).cbrt().round().max(1.0);
+    let hidden = (parameters / (12.0 * layers)).sqrt().max(1.0);
+
+    let trainable = match &lora_config {
+        Some(lora) => {
+            let modules = lora
+                .target_modules
+                .as_ref()
+                .map(|m| m.len() as f64)
+                .unwrap_or(2.0)
+                .min(PROJECTIONS_PER_LAYER);
+            // Each adapted d x d projection gains A (d x r) and B (r x d)
+            2.0 * lora.rank as f64 * hidden * modules * layers
+        }
+        None => parameters,
+    };
+
+    const GB: f64 = 1024.0 * 1024.0 * 1024.0;
+    let weights_gb = parameters * BYTES_PER_WEIGHT / GB;
+    let trainable_state_gb = trainable * BYTES_PER_TRAINABLE_PARAM / GB;
+    let tokens = batch_size as f64 * seq_length as f64;
+    let activations_gb = tokens * hidden * layers * ACTIVATION_BYTES_PER_UNIT / GB;
+    let subtotal = weights_gb + trainable_state_gb + activations_gb;
+    let overhead_gb = BASE_OVERHEAD_GB + OVERHEAD_FRACTION * subtotal;
+    let estimated_vram_gb = subtotal + overhead_gb;
+
+    let gpu_fit = GPU_SIZES
+        .iter()
+        .map(|(gpu, memory_gb)| GpuFit {
+            gpu: gpu.to_string(),
+            memory_gb: *memory_gb,
+            fits: estimated_vram_gb <= *memory_gb,
+            gpus_needed: (estimated_vram_gb / memory_gb).ceil().max(1.0) as u32,
+        })
+        .collect();
+
+    let rationale = format!(
+        "VRAM ~= weights + trainable state + activations + overhead. \
+         Weights: {:.1}B params x {} bytes (bf16). \
+         Trainable state: {:.1}M params x {} bytes \
+         (weights, grads, fp32 master and Adam moments){}. \
+         Activations: batch {} x seq {} x hidden ~{:.0} x layers ~{:.0} x {} bytes \
+         (assumes activation checkpointing). \
+         Overhead: {} GB + {:.0}%. Layer count and hidden size are inferred \
+         from the parameter count, so treat this as a rough guide.",
+        parameters / 1e9,
+        BYTES_PER_WEIGHT,
+        trainable / 1e6,
+        BYTES_PER_TRAINABLE_PARAM,
+        if lora_config.is_some() {
+            "; LoRA adds 2 x rank x hidden per targeted projection per layer"
+        } else {
+            "; full fine-tune"
+        },
+        batch_size,
+        seq_length,
+        hidden,
+        layers,
+        ACTIVATION_BYTES_PER_UNIT,
+        BASE_OVERHEAD_GB,
+        OVERHEAD_FRACTION * 100.0,
+    );
+
+    Ok(ResourceEstimate {
+        model_id,
+        parameters_billions: parameters / 1e9,
+        trainable_parameters_millions: trainable / 1e6,
+        estimated_vram_gb,
+        breakdown: VramBreakdown {
+            weights_gb,
+            trainable_state_gb,
+            activations_gb,
+            overhead_gb,
+        },
+        gpu_fit,
+        rationale,
+    })
+}
+
+/// Look up a model's Tinker metadata, if the model list is reachable
+pub async fn find_model(client: &TinkerClient, model: &str) -> Option<ModelInfo> {
+    match client.get_models().await {
+        Ok(models) => models.into_iter().find(|m| m.id == model),
+        Err(e) => {
+            tracing::debug!("Model list unavailable: {}", e);
+            None
+        }
+    }
+}
+
+/// Parse a parameter count like "8B", "350M" or a model id like "mixtral-8x7b"
+pub fn parse_parameter_count(text: &str) -> Option<f64> {
+    text.to_lowercase()
+        .split(|c: char| c == '-' || c == '_' || c == '/' || c == ':' || c.is_whitespace())
+        .find_map(|token| {
+            let (number, scale) = if let Some(n) = token.strip_suffix('b') {
+                (n, 1e9)
+            } else if let Some(n) = token.strip_suffix('m') {
+                (n, 1e6)
+            } else {
+                return None;
+            };
+            // Mixture-of-experts sizes like "8x7"
+            let value = match number.split_once('x') {
+                Some((experts, size)) => experts.parse::<f64>().ok()? * size.parse::<f64>().ok()?,
+                None => number.parse::<f64>().ok()?,
+            };
+            (value > 0.0).then_some(value * scale)
+        })
+}
+
+// ============ Training Export ============
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreparedDataset {
+    pub path: String,
+    pub records_written: u32,
+    pub duplicates_removed: u32,
+    /// Records dropped because every field was empty
+    pub empty_removed: u32,
+    pub validation: TinkerFormatReport,
+}
+
+/// Normalize a dataset, validate it for a training type and write
+/// upload-ready JSONL to `out_path`
+///
+/// `dataset_id` is a registered dataset id or, failing that, a file path. The
+/// records are held in memory for deduplication and validation, then written
+/// out one line at a time.
+#[tauri::command]
+pub async fn prepare_for_training(
+    state: State<'_, AppState>,
+    dataset_id: String,
+    training_type: String,
+    out_path: String,
+) -> Result<PreparedDataset, String> {
+    let result = prepare_for_training_inner(&state, dataset_id, training_type, out_path).await;
+    state.capture("prepare_for_training", result).await
+}
+
+async fn prepare_for_training_inner(
+    state: &AppState,
+    dataset_id: String,
+    training_type: String,
+    out_path: String,
+) -> Result<PreparedDataset, String> {
+    let training_type = resolve_training_type(&training_type)?;
+    let raw = match registered_examples(state, &dataset_id).await {
+        Some(examples) => examples
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| e.to_string())?,
+        None if std::path::Path::new(&dataset_id).is_file() => read_records(&dataset_id, None)?,
+        None => return Err(format!("Dataset not found: {}", dataset_id)),
+    };
+
+    let NormalizedRecords {
+        records,
+        duplicates_removed,
+        empty_removed,
+    } = normalize_records(raw)?;
+
+    let validation = check_tinker_format(&records, &training_type).into_result()?;
+
+    let file = std::fs::File::create(&out_path)
+        .map_err(|e| format!("Failed to create {}: {}", out_path, e))?;
+    let mut writer = std::io::BufWriter::new(file);
+    write_jsonl(&mut writer, &records)
+        .and_then(|_| writer.flush())
+        .map_err(|e| format!("Failed to write {}: {}", out_path, e))?;
+
+    Ok(PreparedDataset {
+        path: out_path,
+        records_written: records.len() as u32,
+        duplicates_removed,
+        empty_removed,
+        validation,
+    })
+}
+
+/// Records cleaned up for upload, with counts of what was dropped
+struct NormalizedRecords {
+    records: Vec<Value>,
+    duplicates_removed: u32,
+    empty_removed: u32,
+}
+
+/// Normalize each record, drop empty records and exact duplicates, and give
+/// unweighted records a weight of 1.0. Fails on a negative or non-numeric weight
+fn normalize_records(raw: Vec<Value>) -> Result<NormalizedRecords, String> {
+    check_record_weights(&raw)?;
+
+    let mut seen = std::collections::HashSet::new();
+    let mut duplicates_removed = 0;
+    let mut empty_removed = 0;
+    let mut records = Vec::new();
+    for record in raw {
+        let mut record = normalize_record(record);
+        if record.as_object().is_some_and(|obj| obj.is_empty()) {
+            empty_removed += 1;
+            continue;
+        }
+        if let Some(obj) = record.as_object_mut() {
+            obj.entry("weight").or_insert(serde_json::json!(1.0));
+        }
+        if !seen.insert(record.to_string()) {
+            duplicates_removed += 1;
+            continue;
+        }
+        records.push(record);
+    }
+
+    Ok(NormalizedRecords {
+        records,
+        duplicates_removed,
+        empty_removed,
+    })
+}
+
+/// Write records as JSON Lines
+fn write_jsonl(mut writer: impl Write, records: &[Value]) -> std::io::Result<()> {
+    for record in records {
+        serde_json::to_writer(&mut writer, record)?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// Trim string fields and drop empty or null ones
+fn normalize_record(record: Value) -> Value {
+    match record {
+        Value::Object(obj) => Value::Object(
+            obj.into_iter()
+                .filter_map(|(key, value)| match value {
+                    Value::Null => None,
+                    Value::String(text) => {
+                        let text = text.trim();
+                        (!text.is_empty()).then(|| (key, Value::String(text.to_string())))
+                    }
+                    other => Some((key, other)),
+                })
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+// ============ Training Logs ============
+
+/// Delay between log fetches while watching a run
+const LOG_POLL_INTERVAL_MS: u64 = 5000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrainingLogs {
+    pub run_id: String,
+    pub lines: Vec<LogLine>,
+    /// Pass back to fetch only lines after these
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchLogsResult {
+    pub run_id: String,
+    pub lines_emitted: u32,
+    /// Final run status, or the last seen status if the watch was cancelled
+    pub status: String,
+    pub cursor: Option<String>,
+    pub cancelled: bool,
+}
+
+/// Fetch a run's log lines, optionally only those after `cursor`
+#[tauri::command]
+pub async fn get_training_logs(
+    state: State<'_, AppState>,
+    run_id: String,
+    cursor: Option<String>,
+) -> Result<TrainingLogs, String> {
+    let result = get_training_logs_inner(&state, run_id, cursor).await;
+    state.capture("get_training_logs", result).await
+}
+
+async fn get_training_logs_inner(
+    state: &AppState,
+    run_id: String,
+    cursor: Option<String>,
+) -> Result<TrainingLogs, String> {
+    let client = state.tinker.lock().await;
+    let logs = client
+        .get_run_logs(&run_id, cursor.as_deref())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(TrainingLogs {
+        run_id,
+        lines: logs.lines,
+        cursor: logs.next_cursor.or(cursor),
+    })
+}
+
+/// Follow a run's logs until it finishes, emitting new lines as `training-log`
+/// events; stop early with `cancel_log_watch(request_id)`
+#[tauri::command]
+pub async fn watch_training_logs(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    run_id: String,
+    cursor: Option<String>,
+    request_id: String,
+) -> Result<WatchLogsResult, String> {
+    let result = watch_training_logs_inner(app, &state, run_id, cursor, request_id).await;
+    state.capture("watch_training_logs", result).await
+}
+
+async fn watch_training_logs_inner(
+    app: AppHandle,
+    state: &AppState,
+    run_id: String,
+    cursor: Option<String>,
+    request_id: String,
+) -> Result<WatchLogsResult, String> {
+    let cancel = state.register_cancellation(&request_id).await;
+    let watched = follow_logs(&app, state, &run_id, cursor, &cancel).await;
+    state.finish_cancellation(&request_id).await;
+    watched
+}
+
+/// Stop an in-progress `watch_training_logs`
+#[tauri::command]
+pub async fn cancel_log_watch(
+    state: State<'_, AppState>,
+    request_id: String,
+) -> Result<bool, String> {
+    Ok(state.cancel(&request_id).await)
+}
+
+async fn follow_logs(
+    app: &AppHandle,
+    state: &AppState,
+    run_id: &str,
+    mut cursor: Option<String>,
+    cancel: &tokio_util::sync::CancellationToken,
+) -> Result<WatchLogsResult, String> {
+    let mut lines_emitted = 0;
+
+    loop {
+        // Check status before fetching so lines written just before the run
+        // finished are still picked up by this last fetch
+        let (run, logs) = {
+            let client = state.tinker.lock().await;
+            let run = client.get_training_run(run_id).await.map_err(|e| e.to_string())?;
+            let logs = client
+                .get_run_logs(run_id, cursor.as_deref())
+                .await
+                .map_err(|e| e.to_string())?;
+            (run, logs)
+        };
+
+        cursor = logs.next_cursor.or(cursor);
+        if !logs.lines.is_empty() {
+            lines_emitted += logs.lines.len() as u32;
+            let event = TrainingLogs {
+                run_id: run_id.to_string(),
+                lines: logs.lines,
+                cursor: cursor.clone(),
+            };
+            if let Err(e) = app.emit("training-log", event) {
+                tracing::warn!("Failed to emit training log: {}", e);
+            }
+        }
+
+        let finished = matches!(
+            run.status,
+            TrainingStatus::Completed | TrainingStatus::Failed | TrainingStatus::Cancelled
+        );
+        let status = format!("{:?}", run.status).to_lowercase();
+        if finished {
+            return Ok(WatchLogsResult {
+                run_id: run_id.to_string(),
+                lines_emitted,
+                status,
+                cursor,
+                cancelled: false,
+            });
+        }
+
+        tokio::select! {
+            _ = cancel.cancelled() => {
+                return Ok(WatchLogsResult {
+                    run_id: run_id.to_string(),
+                    lines_emitted,
+                    status,
+                    cursor,
+                    cancelled: true,
+                });
+            }
+            _ = tokio::time::sleep(tokio::time::Duration::from_millis(LOG_POLL_INTERVAL_MS)) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model_with_modules(modules: &[&str]) -> ModelInfo {
+        ModelInfo {
+            id: "test-model".to_string(),
+            name: "Test".to_string(),
+            parameters: "1B".to_string(),
+            supported_training_types: vec![TrainingType::Sft],
+            max_lora_rank: 64,
+            price_per_million_tokens: 1.0,
+            target_modules: modules.iter().map(|m| m.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn maps_default_modules_by_role_on_bert_style_models() {
+        let model = model_with_modules(&["query", "key", "value", "dense"]);
+        let check = resolve_target_modules(Some(&model), None).unwrap();
+
+        assert_eq!(check.target_modules, vec!["query", "value"]);
+        assert_eq!(check.corrections.len(), 2);
+    }
+
+    #[test]
+    fn maps_separate_projections_onto_fused_qkv_only_when_needed() {
+        let model = model_with_modules(&["query_key_value", "dense"]);
+        let requested = vec!["q_proj".to_string(), "v_proj".to_string(), "o_proj".to_string()];
+        let check = resolve_target_modules(Some(&model), Some(requested)).unwrap();
+
+        assert_eq!(check.target_modules, vec!["query_key_value", "dense"]);
+    }
+
+    #[test]
+    fn expands_fused_qkv_onto_separate_projections() {
+        let model = model_with_modules(&["q_proj", "k_proj", "v_proj", "o_proj"]);
+        let check =
+            resolve_target_modules(Some(&model), Some(vec!["c_attn".to_string()])).unwrap();
+
+        assert_eq!(check.target_modules, vec!["q_proj", "k_proj", "v_proj"]);
+    }
+
+    #[test]
+    fn keeps_valid_modules_and_rejects_unknown_ones() {
+        let model = model_with_modules(&["q_proj", "v_proj"]);
+        let check = resolve_target_modules(Some(&model), None).unwrap();
+        assert_eq!(check.target_modules, vec!["q_proj", "v_proj"]);
+        assert!(check.corrections.is_empty());
+
+        let unknown = resolve_target_modules(Some(&model), Some(vec!["lm_head".to_string()]));
+        assert!(unknown.is_err());
+    }
+
+    #[test]
+    fn passes_request_through_without_model_modules() {
+        let check = resolve_target_modules(None, Some(vec!["anything".to_string()])).unwrap();
+        assert_eq!(check.target_modules, vec!["anything"]);
+    }
+
+    #[test]
+    fn normalizing_defaults_weights_and_rejects_negative_ones() {
+        let raw = vec![
+            serde_json::json!({"input": " hi ", "output": "there"}),
+            serde_json::json!({"input": "hi", "output": "there", "weight": 1.0}),
+            serde_json::json!({"input": "", "output": null}),
+        ];
+        let normalized = normalize_records(raw).unwrap();
+        assert_eq!(
+            normalized.records,
+            vec![serde_json::json!({"input": "hi", "output": "there", "weight": 1.0})]
+        );
+        assert_eq!(normalized.duplicates_removed, 1);
+        assert_eq!(normalized.empty_removed, 1);
+
+        let negative = vec![serde_json::json!({"input": "a", "output": "b", "weight": -0.5})];
+        assert!(normalize_records(negative).is_err());
+    }
+}
@@ -1,7 +1,9 @@
 //! Voice commands for ElevenLabs integration
 
-use crate::api::elevenlabs::{Voice, VoiceSettings};
-use crate::state::AppState;
+use crate::api::elevenlabs::{self, AudioInfo, Voice, VoiceQuota, VoiceSettings};
+use crate::commands::data::TrainingExample;
+use crate::state::{AppState, TranscriptRecord};
+use futures::stream::{self, StreamExt};
 use serde::{Deserialize, Serialize};
 use tauri::State;
 
@@ -10,12 +12,19 @@ pub struct TranscriptionResponse {
     pub text: String,
     pub confidence: Option<f32>,
     pub language_code: Option<String>,
+    pub audio: AudioInfo,
+    /// Recording quality caveat (e.g. low sample rate); the clip is still transcribed
+    pub quality_warning: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SpeechResponse {
     pub audio_base64: String,
     pub content_type: String,
+    /// Interrupted via `cancel_speech`; audio covers only the completed chunks
+    pub cancelled: bool,
+    /// Voice/model compatibility concern detected before synthesis
+    pub warning: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -29,6 +38,14 @@ pub struct VoiceStatus {
 pub async fn transcribe_audio(
     state: State<'_, AppState>,
     audio_base64: String,
+) -> Result<TranscriptionResponse, String> {
+    let result = transcribe_audio_inner(&state, audio_base64).await;
+    state.capture("transcribe_audio", result).await
+}
+
+async fn transcribe_audio_inner(
+    state: &AppState,
+    audio_base64: String,
 ) -> Result<TranscriptionResponse, String> {
     let client = state.elevenlabs.lock().await;
 
@@ -36,35 +53,330 @@ pub async fn transcribe_audio(
         .transcribe(&audio_base64)
         .await
         .map_err(|e| e.to_string())?;
+    state
+        .record_transcript(&result.text, result.confidence, result.language_code.as_deref())
+        .await;
 
     Ok(TranscriptionResponse {
         text: result.text,
         confidence: result.confidence,
         language_code: result.language_code,
+        audio: result.audio,
+        quality_warning: result.quality_warning,
     })
 }
 
+/// Turn transcript logging on or off; returns the previous setting
+#[tauri::command]
+pub async fn set_transcript_logging(
+    state: State<'_, AppState>,
+    enabled: bool,
+) -> Result<bool, String> {
+    let mut log = state.transcripts.lock().await;
+    Ok(std::mem::replace(&mut log.enabled, enabled))
+}
+
+/// Logged transcripts, oldest first, optionally clearing the log
+#[tauri::command]
+pub async fn export_transcripts(
+    state: State<'_, AppState>,
+    clear: Option<bool>,
+) -> Result<Vec<TranscriptRecord>, String> {
+    let mut log = state.transcripts.lock().await;
+    if clear.unwrap_or(false) {
+        Ok(log.entries.drain(..).collect())
+    } else {
+        Ok(log.entries.iter().cloned().collect())
+    }
+}
+
 /// Convert text to speech
+///
+/// Pass a `request_id` to make synthesis interruptible with `cancel_speech`.
 #[tauri::command]
 pub async fn text_to_speech(
     state: State<'_, AppState>,
     text: String,
     voice_id: Option<String>,
     voice_settings: Option<VoiceSettings>,
+    request_id: Option<String>,
 ) -> Result<SpeechResponse, String> {
+    let result = text_to_speech_inner(&state, text, voice_id, voice_settings, request_id).await;
+    state.capture("text_to_speech", result).await
+}
+
+async fn text_to_speech_inner(
+    state: &AppState,
+    text: String,
+    voice_id: Option<String>,
+    voice_settings: Option<VoiceSettings>,
+    request_id: Option<String>,
+) -> Result<SpeechResponse, String> {
+    let cancel = match &request_id {
+        Some(id) => Some(state.register_cancellation(id).await),
+        None => None,
+    };
+
+    let result = {
+        let client = state.elevenlabs.lock().await;
+        client
+            .text_to_speech(&text, voice_id.as_deref(), voice_settings, cancel.as_ref())
+            .await
+    };
+
+    if let Some(id) = &request_id {
+        state.finish_cancellation(id).await;
+    }
+
+    let result = result.map_err(|e| e.to_string())?;
+
+    Ok(SpeechResponse {
+        audio_base64: result.audio_base64,
+        content_type: result.content_type,
+        cancelled: result.cancelled,
+        warning: result.warning,
+    })
+}
+
+/// Interrupt an in-progress `text_to_speech` call (e.g. when the user barges in)
+#[tauri::command]
+pub async fn cancel_speech(state: State<'_, AppState>, request_id: String) -> Result<bool, String> {
+    Ok(state.cancel(&request_id).await)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TtsEstimate {
+    pub model_id: String,
+    pub character_count: u32,
+    /// Number of requests the text will be split into
+    pub chunk_count: u32,
+    pub estimated_credits: f64,
+    pub estimated_cost_usd: f64,
+}
+
+/// Estimate TTS size and cost locally, without calling ElevenLabs
+#[tauri::command]
+pub async fn estimate_tts(
+    state: State<'_, AppState>,
+    text: String,
+    model_id: Option<String>,
+) -> Result<TtsEstimate, String> {
+    let model_id = match model_id {
+        Some(model_id) => model_id,
+        None => state.elevenlabs.lock().await.default_model_id().to_string(),
+    };
+
+    let character_count = text.chars().count() as u32;
+    let chunk_count = elevenlabs::chunk_text(&text, elevenlabs::model_char_limit(&model_id)).len() as u32;
+    let estimated_credits = character_count as f64 * elevenlabs::credits_per_character(&model_id);
+
+    Ok(TtsEstimate {
+        model_id,
+        character_count,
+        chunk_count,
+        estimated_credits,
+        estimated_cost_usd: elevenlabs::credits_to_usd(estimated_credits),
+    })
+}
+
+/// Maximum number of settings candidates synthesized per tuning request
+const MAX_TUNING_CANDIDATES: usize = 4;
+/// Maximum length of the tuning sample text, to keep each clip cheap
+const MAX_TUNING_SAMPLE_CHARS: usize = 500;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VoiceSample {
+    pub settings: VoiceSettings,
+    pub audio_base64: Option<String>,
+    pub content_type: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Synthesize the same sample with several settings so they can be A/B compared
+#[tauri::command]
+pub async fn tune_voice_settings(
+    state: State<'_, AppState>,
+    voice_id: Option<String>,
+    sample_text: String,
+    candidates: Vec<VoiceSettings>,
+) -> Result<Vec<VoiceSample>, String> {
+    let result = tune_voice_settings_inner(&state, voice_id, sample_text, candidates).await;
+    state.capture("tune_voice_settings", result).await
+}
+
+async fn tune_voice_settings_inner(
+    state: &AppState,
+    voice_id: Option<String>,
+    sample_text: String,
+    candidates: Vec<VoiceSettings>,
+) -> Result<Vec<VoiceSample>, String> {
+    if candidates.is_empty() {
+        return Err("At least one candidate setting is required".to_string());
+    }
+    if candidates.len() > MAX_TUNING_CANDIDATES {
+        return Err(format!(
+            "Too many candidates: {} (max {})",
+            candidates.len(),
+            MAX_TUNING_CANDIDATES
+        ));
+    }
+    if sample_text.chars().count() > MAX_TUNING_SAMPLE_CHARS {
+        return Err(format!(
+            "Sample text is too long (max {} characters)",
+            MAX_TUNING_SAMPLE_CHARS
+        ));
+    }
+
     let client = state.elevenlabs.lock().await;
+    let mut samples = Vec::with_capacity(candidates.len());
+    for settings in candidates {
+        let sample = match client
+            .text_to_speech(&sample_text, voice_id.as_deref(), Some(settings.clone()), None)
+            .await
+        {
+            Ok(speech) => VoiceSample {
+                settings,
+                audio_base64: Some(speech.audio_base64),
+                content_type: Some(speech.content_type),
+                error: None,
+            },
+            Err(e) => VoiceSample {
+                settings,
+                audio_base64: None,
+                content_type: None,
+                error: Some(e.to_string()),
+            },
+        };
+        samples.push(sample);
+    }
 
-    let result = client
-        .text_to_speech(&text, voice_id.as_deref(), voice_settings)
+    Ok(samples)
+}
+
+/// Most examples synthesized by one `synthesize_dataset_sample` call
+const MAX_AUDITION_EXAMPLES: usize = 10;
+/// Concurrent ElevenLabs requests while synthesizing a sample
+const MAX_CONCURRENT_SYNTHESIS: usize = 3;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExampleAudio {
+    pub index: u32,
+    pub audio_base64: Option<String>,
+    pub content_type: Option<String>,
+    pub warning: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Speak a training example's output so it can be auditioned
+#[tauri::command]
+pub async fn synthesize_example(
+    state: State<'_, AppState>,
+    example: TrainingExample,
+    voice_id: Option<String>,
+    settings: Option<VoiceSettings>,
+) -> Result<SpeechResponse, String> {
+    let result = synthesize_example_inner(&state, example, voice_id, settings).await;
+    state.capture("synthesize_example", result).await
+}
+
+async fn synthesize_example_inner(
+    state: &AppState,
+    example: TrainingExample,
+    voice_id: Option<String>,
+    settings: Option<VoiceSettings>,
+) -> Result<SpeechResponse, String> {
+    if example.output.trim().is_empty() {
+        return Err("Example has no output to synthesize".to_string());
+    }
+
+    let client = state.elevenlabs.lock().await;
+    let speech = client
+        .text_to_speech(&example.output, voice_id.as_deref(), settings, None)
         .await
         .map_err(|e| e.to_string())?;
 
     Ok(SpeechResponse {
-        audio_base64: result.audio_base64,
-        content_type: result.content_type,
+        audio_base64: speech.audio_base64,
+        content_type: speech.content_type,
+        cancelled: speech.cancelled,
+        warning: speech.warning,
     })
 }
 
+/// Speak the outputs of a few selected examples, a bounded number at a time
+#[tauri::command]
+pub async fn synthesize_dataset_sample(
+    state: State<'_, AppState>,
+    examples: Vec<TrainingExample>,
+    indices: Vec<u32>,
+    voice_id: Option<String>,
+    settings: Option<VoiceSettings>,
+) -> Result<Vec<ExampleAudio>, String> {
+    let result = synthesize_dataset_sample_inner(
+        &state,
+        examples,
+        indices,
+        voice_id,
+        settings,
+    )
+    .await;
+    state.capture("synthesize_dataset_sample", result).await
+}
+
+async fn synthesize_dataset_sample_inner(
+    state: &AppState,
+    examples: Vec<TrainingExample>,
+    indices: Vec<u32>,
+    voice_id: Option<String>,
+    settings: Option<VoiceSettings>,
+) -> Result<Vec<ExampleAudio>, String> {
+    if indices.is_empty() {
+        return Err("At least one example index is required".to_string());
+    }
+    if indices.len() > MAX_AUDITION_EXAMPLES {
+        return Err(format!(
+            "Too many examples: {} (max {})",
+            indices.len(),
+            MAX_AUDITION_EXAMPLES
+        ));
+    }
+    if let Some(index) = indices.iter().find(|i| **i as usize >= examples.len()) {
+        return Err(format!("Example index {} is out of range", index));
+    }
+
+    let client = state.elevenlabs.lock().await;
+    let samples = stream::iter(indices)
+        .map(|index| {
+            let client = &client;
+            let text = examples[index as usize].output.as_str();
+            let voice_id = voice_id.as_deref();
+            let settings = settings.clone();
+            async move {
+                match client.text_to_speech(text, voice_id, settings, None).await {
+                    Ok(speech) => ExampleAudio {
+                        index,
+                        audio_base64: Some(speech.audio_base64),
+                        content_type: Some(speech.content_type),
+                        warning: speech.warning,
+                        error: None,
+                    },
+                    Err(e) => ExampleAudio {
+                        index,
+                        audio_base64: None,
+                        content_type: None,
+                        warning: None,
+                        error: Some(e.to_string()),
+                    },
+                }
+            }
+        })
+        .buffered(MAX_CONCURRENT_SYNTHESIS)
+        .collect()
+        .await;
+
+    Ok(samples)
+}
+
 /// Get voice configuration status
 #[tauri::command]
 pub async fn get_voice_status(state: State<'_, AppState>) -> Result<VoiceStatus, String> {
@@ -76,10 +388,28 @@ pub async fn get_voice_status(state: State<'_, AppState>) -> Result<VoiceStatus,
     })
 }
 
+/// Remaining ElevenLabs character quota, for usage meters and pre-synthesis warnings
+#[tauri::command]
+pub async fn get_voice_quota(state: State<'_, AppState>) -> Result<VoiceQuota, String> {
+    let result = get_voice_quota_inner(&state).await;
+    state.capture("get_voice_quota", result).await
+}
+
+async fn get_voice_quota_inner(state: &AppState) -> Result<VoiceQuota, String> {
+    let client = state.elevenlabs.lock().await;
+
+    client.get_quota().await.map_err(|e| e.to_string())
+}
+
 /// List available voices
 #[tauri::command]
 pub async fn list_voices(state: State<'_, AppState>) -> Result<Vec<Voice>, String> {
-    let client = state.elevenlabs.lock().await;
+    let result = list_voices_inner(&state).await;
+    state.capture("list_voices", result).await
+}
+
+async fn list_voices_inner(state: &AppState) -> Result<Vec<Voice>, String> {
+    let mut client = state.elevenlabs.lock().await;
 
     client.list_voices().await.map_err(|e| e.to_string())
 }
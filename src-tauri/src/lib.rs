@@ -19,9 +19,16 @@ pub fn run() {
         .plugin(tauri_plugin_shell::init())
         .setup(|app| {
             // Initialize app state with API clients
-            let state = AppState::new();
+            let mut state = AppState::new();
+            commands::agents::restore_agent_prompts(app.handle(), &mut state);
+            commands::agents::restore_agent_settings(app.handle(), &mut state);
             app.manage(state);
 
+            // Pick up research that was still running when the app last closed
+            tauri::async_runtime::spawn(commands::research::resume_pending_research(
+                app.handle().clone(),
+            ));
+
             #[cfg(debug_assertions)]
             {
                 let window = app.get_webview_window("main").unwrap();
@@ -33,32 +40,101 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             // Voice commands
             commands::voice::transcribe_audio,
+            commands::voice::set_transcript_logging,
+            commands::voice::export_transcripts,
             commands::voice::text_to_speech,
+            commands::voice::cancel_speech,
+            commands::voice::estimate_tts,
+            commands::voice::tune_voice_settings,
+            commands::voice::synthesize_example,
+            commands::voice::synthesize_dataset_sample,
             commands::voice::get_voice_status,
+            commands::voice::get_voice_quota,
             commands::voice::list_voices,
             // Agent commands
             commands::agents::parse_intent,
+            commands::agents::replay_transcript,
             commands::agents::validate_data,
+            commands::agents::revalidate,
+            commands::agents::rank_datasets,
             commands::agents::recommend_config,
+            commands::agents::refine_config,
+            commands::agents::research_to_brief,
             commands::agents::chat_with_agent,
+            commands::agents::chat_stream,
+            commands::agents::cancel_chat,
+            commands::agents::get_agent_prompt,
+            commands::agents::set_agent_prompt,
+            commands::agents::reset_agent_prompt,
+            commands::agents::get_agent_usage,
+            commands::agents::get_agent_settings,
+            commands::agents::set_agent_settings,
             // Data commands
             commands::data::generate_synthetic_data,
+            commands::data::generate_multi_domain,
+            commands::data::preview_schema_generation,
+            commands::data::recommended_batch_size,
+            commands::data::get_generation_status,
             commands::data::upload_dataset,
+            commands::data::detect_csv_columns,
+            commands::data::verify_conversion,
+            commands::data::append_to_remote_dataset,
             commands::data::preview_dataset,
+            commands::data::preview_file,
             commands::data::get_dataset_stats,
+            commands::data::check_split_leakage,
+            commands::data::get_token_counts,
+            commands::data::update_dataset,
+            commands::data::list_dataset_versions,
+            commands::data::revert_dataset,
+            commands::data::remove_dataset,
+            commands::data::check_length_balance,
+            commands::data::analyze_system_prompts,
+            commands::data::extract_common_system,
+            commands::data::field_stats,
+            commands::data::recommend_seq_length,
             // Research commands
             commands::research::research_domain,
             commands::research::get_research_status,
+            commands::research::list_pending_research,
+            commands::research::set_research_polling,
             // Training commands
+            commands::training::normalize_training_type,
+            commands::training::validate_tinker_format,
+            commands::training::prepare_for_training,
             commands::training::create_training_run,
+            commands::training::create_and_watch_training_run,
+            commands::training::cancel_run_watch,
+            commands::training::validate_target_modules,
             commands::training::get_training_run,
             commands::training::list_training_runs,
             commands::training::get_training_status,
             commands::training::cancel_training_run,
+            commands::training::get_run_results,
+            commands::training::get_training_logs,
+            commands::training::watch_training_logs,
+            commands::training::cancel_log_watch,
+            commands::training::sweep_example_counts,
+            commands::training::recommend_model,
+            commands::training::plan_workflow,
+            commands::training::save_config_template,
+            commands::training::list_config_templates,
+            commands::training::apply_config_template,
+            commands::training::config_diff,
+            commands::training::export_run_config_code,
+            commands::training::import_run_config_code,
+            commands::training::estimate_resources,
             // Settings commands
             commands::settings::get_api_keys_status,
+            commands::settings::rotate_api_key,
             commands::settings::set_api_key,
             commands::settings::test_api_connection,
+            commands::settings::check_all_connections,
+            commands::settings::pipeline_readiness,
+            // Diagnostics commands
+            commands::diagnostics::latency_probe,
+            commands::diagnostics::recent_errors,
+            commands::diagnostics::diagnostics_bundle,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -1,12 +1,66 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use serde::Serialize;
 use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
 
 use crate::api::{
-    anthropic::AnthropicClient,
+    anthropic::{AgentType, AnthropicClient, Message, Usage},
     elevenlabs::ElevenLabsClient,
     tinker::TinkerClient,
     tonic::TonicClient,
     yutori::YutoriClient,
 };
+use crate::commands::data::{RegisteredDataset, TrainingExample};
+use crate::commands::settings::ConnectionCheck;
+
+/// File used by tauri-plugin-store for persisted app data
+pub const STORE_FILE: &str = "settings.json";
+
+/// Number of command failures kept for `recent_errors`
+pub const ERROR_LOG_CAPACITY: usize = 100;
+
+/// A command failure captured for diagnostics
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorRecord {
+    pub timestamp: String,
+    pub command: String,
+    /// Error message with anything resembling a secret redacted
+    pub message: String,
+}
+
+/// Datasets kept in the registry before the least recently used are dropped
+pub const MAX_REGISTERED_DATASETS: usize = 16;
+/// Approximate text size kept across all registered datasets
+pub const MAX_REGISTRY_BYTES: usize = 500 * 1024 * 1024;
+
+/// Number of transcripts kept while transcript logging is enabled
+pub const TRANSCRIPT_LOG_CAPACITY: usize = 500;
+
+/// A `transcribe_audio` result kept for replaying through intent parsing
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptRecord {
+    pub timestamp: String,
+    pub text: String,
+    pub confidence: Option<f32>,
+    pub language_code: Option<String>,
+}
+
+/// Opt-in log of transcripts, oldest first
+#[derive(Debug, Default)]
+pub struct TranscriptLog {
+    pub enabled: bool,
+    pub entries: VecDeque<TranscriptRecord>,
+}
+
+/// Claude calls and tokens attributed to one agent this session
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AgentUsage {
+    pub calls: u32,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub estimated_cost_usd: f64,
+}
 
 /// Shared application state accessible from all Tauri commands
 pub struct AppState {
@@ -15,6 +69,20 @@ pub struct AppState {
     pub tonic: Mutex<TonicClient>,
     pub yutori: Mutex<YutoriClient>,
     pub tinker: Mutex<TinkerClient>,
+    /// Cancellation tokens for in-flight interruptible operations, keyed by request id
+    pub cancellations: Mutex<HashMap<String, CancellationToken>>,
+    /// Most recent command failures, oldest first
+    pub errors: Mutex<VecDeque<ErrorRecord>>,
+    /// Running conversation with the Config agent for `refine_config`
+    pub config_conversation: Mutex<Vec<Message>>,
+    /// Datasets ingested this session, keyed by dataset id
+    pub datasets: Mutex<HashMap<String, RegisteredDataset>>,
+    /// Transcripts recorded when logging is switched on with `set_transcript_logging`
+    pub transcripts: Mutex<TranscriptLog>,
+    /// Claude usage totals per agent, for `get_agent_usage`
+    pub agent_usage: Mutex<HashMap<AgentType, AgentUsage>>,
+    /// Latest `check_all_connections` result per service
+    pub connection_checks: Mutex<BTreeMap<String, ConnectionCheck>>,
 }
 
 impl AppState {
@@ -32,6 +100,138 @@ impl AppState {
             tonic: Mutex::new(TonicClient::new(tonic_key)),
             yutori: Mutex::new(YutoriClient::new(yutori_key)),
             tinker: Mutex::new(TinkerClient::new(tinker_key)),
+            cancellations: Mutex::new(HashMap::new()),
+            errors: Mutex::new(VecDeque::with_capacity(ERROR_LOG_CAPACITY)),
+            config_conversation: Mutex::new(Vec::new()),
+            datasets: Mutex::new(HashMap::new()),
+            transcripts: Mutex::new(TranscriptLog::default()),
+            agent_usage: Mutex::new(HashMap::new()),
+            connection_checks: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Keep an ingested dataset (and its token counts) for later commands,
+    /// evicting the least recently used ones once the registry is over its caps
+    pub async fn register_dataset(&self, id: &str, examples: Vec<TrainingExample>) {
+        let mut datasets = self.datasets.lock().await;
+        datasets.insert(id.to_string(), RegisteredDataset::new(examples));
+
+        let total = |datasets: &HashMap<String, RegisteredDataset>| -> usize {
+            datasets.values().map(RegisteredDataset::size_bytes).sum()
+        };
+        while datasets.len() > MAX_REGISTERED_DATASETS
+            || (datasets.len() > 1 && total(&datasets) > MAX_REGISTRY_BYTES)
+        {
+            let Some(oldest) = datasets
+                .iter()
+                .filter(|(key, _)| key.as_str() != id)
+                .min_by_key(|(_, dataset)| dataset.last_used())
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            tracing::info!("Evicting registered dataset {}", oldest);
+            datasets.remove(&oldest);
+        }
+    }
+
+    /// Drop a registered dataset and its history; returns false if it wasn't held
+    pub async fn remove_dataset(&self, id: &str) -> bool {
+        self.datasets.lock().await.remove(id).is_some()
+    }
+
+    /// Record a command's failure (if any) and pass the result through
+    pub async fn capture<T>(&self, command: &str, result: Result<T, String>) -> Result<T, String> {
+        if let Err(e) = &result {
+            self.record_error(command, e).await;
+        }
+        result
+    }
+
+    /// Append a failure to the error log, evicting the oldest entry when full
+    pub async fn record_error(&self, command: &str, message: &str) {
+        let mut errors = self.errors.lock().await;
+        if errors.len() == ERROR_LOG_CAPACITY {
+            errors.pop_front();
+        }
+        errors.push_back(ErrorRecord {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            command: command.to_string(),
+            message: redact_secrets(message),
+        });
+    }
+
+    /// Most recent failures, newest first
+    pub async fn recent_errors(&self, limit: usize) -> Vec<ErrorRecord> {
+        self.errors.lock().await.iter().rev().take(limit).cloned().collect()
+    }
+
+    /// Keep a transcript if logging is enabled, evicting the oldest entry when full
+    pub async fn record_transcript(
+        &self,
+        text: &str,
+        confidence: Option<f32>,
+        language_code: Option<&str>,
+    ) {
+        let mut log = self.transcripts.lock().await;
+        if !log.enabled {
+            return;
+        }
+        if log.entries.len() == TRANSCRIPT_LOG_CAPACITY {
+            log.entries.pop_front();
+        }
+        log.entries.push_back(TranscriptRecord {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            text: text.to_string(),
+            confidence,
+            language_code: language_code.map(str::to_string),
+        });
+    }
+
+    /// Attribute a Claude call to an agent; calls without reported usage
+    /// still count toward the call total
+    pub async fn record_usage(&self, agent: AgentType, usage: Option<&Usage>) {
+        let mut totals = self.agent_usage.lock().await;
+        let entry = totals.entry(agent).or_default();
+        entry.calls += 1;
+        if let Some(usage) = usage {
+            entry.input_tokens += usage.input_tokens as u64;
+            entry.output_tokens += usage.output_tokens as u64;
+            entry.estimated_cost_usd += usage.estimated_cost_usd();
+        }
+    }
+
+    /// Remember connection test results, replacing earlier ones for the same services
+    pub async fn record_connection_checks(&self, checks: &[ConnectionCheck]) {
+        let mut latest = self.connection_checks.lock().await;
+        for check in checks {
+            latest.insert(check.service.clone(), check.clone());
+        }
+    }
+
+    /// Register a cancellable operation under a caller-chosen id
+    pub async fn register_cancellation(&self, request_id: &str) -> CancellationToken {
+        let token = CancellationToken::new();
+        self.cancellations
+            .lock()
+            .await
+            .insert(request_id.to_string(), token.clone());
+        token
+    }
+
+    /// Forget an operation's token once it has finished
+    pub async fn finish_cancellation(&self, request_id: &str) {
+        self.cancellations.lock().await.remove(request_id);
+    }
+
+    /// Signal an in-flight operation to stop; returns false if it isn't running
+    pub async fn cancel(&self, request_id: &str) -> bool {
+        match self.cancellations.lock().await.get(request_id) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
         }
     }
 }
@@ -41,3 +241,79 @@ impl Default for AppState {
         Self::new()
     }
 }
+
+/// Names whose assigned value (`name=value`, `"name": "value"`) is treated as a secret
+const SECRET_NAME_HINTS: &[&str] = &["key", "token", "secret", "password", "authorization"];
+
+/// Mask tokens that look like API keys or bearer credentials, plus values
+/// assigned to secret-sounding names. Works on any separators, so keys inside
+/// JSON bodies, query strings and multi-line output are caught too
+pub fn redact_secrets(message: &str) -> String {
+    let is_token_char = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+    let mut redacted = String::with_capacity(message.len());
+    let mut rest = message;
+    let mut value_expected = false;
+
+    while !rest.is_empty() {
+        let token_len = rest.find(|c: char| !is_token_char(c)).unwrap_or(rest.len());
+        let (token, after) = rest.split_at(token_len);
+        let separator_len = after.find(is_token_char).unwrap_or(after.len());
+        let (separator, next) = after.split_at(separator_len);
+
+        let lower = token.to_ascii_lowercase();
+        let looks_like_key = token.starts_with("sk-")
+            || (token.len() >= 24
+                && token.chars().any(|c| c.is_ascii_digit())
+                && token.chars().any(|c| c.is_ascii_alphabetic()));
+        let is_bearer = lower == "bearer";
+
+        if !token.is_empty() && !is_bearer && (value_expected || looks_like_key) {
+            redacted.push_str("[redacted]");
+        } else {
+            redacted.push_str(token);
+        }
+        redacted.push_str(separator);
+
+        let assigns = matches!(
+            separator.trim_matches(|c: char| c.is_whitespace() || c == '"' || c == '\''),
+            "=" | ":"
+        );
+        // "Authorization: Bearer <key>" keeps expecting the key after "Bearer"
+        let bearer_value = value_expected || separator.chars().all(char::is_whitespace);
+        value_expected = (is_bearer && bearer_value)
+            || (assigns && SECRET_NAME_HINTS.iter().any(|hint| lower.contains(hint)));
+        rest = next;
+    }
+    redacted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_keys_in_plain_text() {
+        assert_eq!(
+            redact_secrets("invalid key sk-ant-abc123 for\tBearer abcdef\nrequest"),
+            "invalid key [redacted] for\tBearer [redacted]\nrequest"
+        );
+    }
+
+    #[test]
+    fn redacts_keys_in_json_and_assignments() {
+        assert_eq!(
+            redact_secrets(r#"{"api_key":"sk-abc","detail":"bad"} token=hunter2&page=1"#),
+            r#"{"api_key":"[redacted]","detail":"bad"} token=[redacted]&page=1"#
+        );
+        assert_eq!(
+            redact_secrets("Authorization: Bearer hunter2"),
+            "Authorization: Bearer [redacted]"
+        );
+    }
+
+    #[test]
+    fn leaves_ordinary_text_alone() {
+        let message = "HTTP 401: key is not valid for model claude-3-haiku";
+        assert_eq!(redact_secrets(message), message);
+    }
+}